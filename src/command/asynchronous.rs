@@ -0,0 +1,369 @@
+//! `command::*` 函数的异步版本，需要开启 `async` feature
+//!
+//! 函数名和参数与同步版本一致，通过 `tokio::process` 执行，`Future` 被取消时会杀掉 `brew` 进程
+//!
+//! Examples
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use homebrew::{MockExecutor, MockResponse};
+//! # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+//! #     .on(["info", "wget", "--json=v2"], MockResponse::stdout_file("examples/data/wget.json").unwrap())
+//! #     .on(["services", "--json"], MockResponse::stdout_file("examples/data/services.json").unwrap())));
+//! use homebrew::asynchronous as brew;
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let pkg = brew::info("wget").await.unwrap();
+//! assert_eq!(pkg.name, "wget");
+//!
+//! let srvs = brew::services().await.unwrap();
+//! assert_eq!(srvs.last().unwrap().name, "unbound");
+//! # })
+//! ```
+use crate::command::autoremove::check_plan;
+use crate::command::service::first_info;
+use crate::error::Result;
+use crate::trace::from_json;
+use crate::{Brew, BrewEvent, BrewOutput, Config, Dependent, Env, Orphan, Outdated, Package, RemovedKeg, Service, ServiceInfo};
+
+/// [`crate::info`] 的异步版本
+pub async fn info(name: &str) -> Result<Package> {
+    let out = Brew::default()
+        .args(["info", name, "--json=v2"])
+        .output_async().await?;
+    Ok(Package::from(&out)?)
+}
+
+/// [`crate::info_all`] 的异步版本
+pub async fn info_all() -> Result<Package> {
+    let out = Brew::default()
+        .args(["info", "--eval-all", "--json=v2"])
+        .output_async().await?;
+    Ok(Package::from_all(&out)?)
+}
+
+/// [`crate::info_installed`] 的异步版本
+pub async fn info_installed() -> Result<Package> {
+    let out = Brew::default()
+        .args(["info", "--installed", "--json=v2"])
+        .output_async().await?;
+    Ok(Package::from_all(&out)?)
+}
+
+/// [`crate::uses`] 的异步版本
+pub async fn uses(name: &str, installed_only: bool, recursive: bool) -> Result<Vec<Dependent>> {
+    let pkg = if installed_only { info_installed().await? } else { info_all().await? };
+    Ok(pkg.uses(name, installed_only, recursive))
+}
+
+/// [`crate::uses_installed`] 的异步版本
+pub async fn uses_installed(name: &str, recursive: bool) -> Result<Vec<Dependent>> {
+    let mut brew = Brew::default();
+    brew.args(["uses", "--installed"]);
+    if recursive {
+        brew.arg("--recursive");
+    }
+    let names = brew.arg(name).output_vec_async().await?;
+    Ok(info_installed().await?.annotate_uses(name, recursive, &names))
+}
+
+/// [`crate::leaves`] 的异步版本
+pub async fn leaves(installed_on_request: bool) -> Result<Vec<String>> {
+    let mut brew = Brew::default();
+    brew.arg("leaves");
+    if installed_on_request {
+        brew.arg("--installed-on-request");
+    }
+    brew.output_vec_async().await
+}
+
+/// [`crate::autoremove_plan`] 的异步版本
+pub async fn autoremove_plan() -> Result<Vec<Orphan>> {
+    Ok(info_installed().await?.autoremove_plan())
+}
+
+/// [`crate::autoremove`] 的异步版本
+pub async fn autoremove(plan: &[Orphan]) -> Result<Vec<RemovedKeg>> {
+    check_plan(plan, &autoremove_plan().await?)?;
+    let out = Brew::default()
+        .arg("autoremove")
+        .run_async().await?;
+    Ok(out.stdout.lines().filter_map(RemovedKeg::parse).collect())
+}
+
+/// [`crate::outdated`] 的异步版本
+pub async fn outdated(greedy: bool) -> Result<Vec<Outdated>> {
+    let mut brew = Brew::default();
+    brew.args(["outdated", "--json=v2"]);
+    if greedy {
+        brew.arg("--greedy");
+    }
+    let out = brew.output_async().await?;
+    Ok(Outdated::from(&out)?)
+}
+
+/// [`crate::search`] 的异步版本
+pub async fn search(name: &str) -> Result<Vec<String>> {
+    Brew::default()
+        .args(["search", name])
+        .output_vec_async().await
+}
+
+/// [`crate::list`] 的异步版本
+pub async fn list() -> Result<Vec<String>> {
+    Brew::default()
+        .arg("list")
+        .output_vec_async().await
+}
+
+/// [`crate::list_cask`] 的异步版本
+pub async fn list_cask() -> Result<Vec<String>> {
+    Brew::default()
+        .args(["list", "--cask"])
+        .output_vec_async().await
+}
+
+/// [`crate::list_formulae`] 的异步版本
+pub async fn list_formulae() -> Result<Vec<String>> {
+    Brew::default()
+        .args(["list", "--formula"])
+        .output_vec_async().await
+}
+
+/// [`crate::config()`] 的异步版本
+pub async fn config() -> Result<Config> {
+    let output = Brew::default()
+        .arg("config")
+        .output_async().await?;
+    Ok(Config::from(&output)?)
+}
+
+/// [`crate::cache`] 的异步版本
+pub async fn cache() -> Result<String> {
+    Brew::default()
+        .arg("--cache")
+        .output_async().await
+}
+
+/// [`crate::version`] 的异步版本
+pub async fn version() -> Result<String> {
+    Brew::default()
+        .arg("--version")
+        .output_async().await
+}
+
+/// [`crate::repository`] 的异步版本
+pub async fn repository() -> Result<String> {
+    Brew::default()
+        .arg("--repository")
+        .output_async().await
+}
+
+/// [`crate::prefix`] 的异步版本
+pub async fn prefix() -> Result<String> {
+    Brew::default()
+        .arg("--prefix")
+        .output_async().await
+}
+
+/// [`crate::caskroom`] 的异步版本
+pub async fn caskroom() -> Result<String> {
+    Brew::default()
+        .arg("--caskroom")
+        .output_async().await
+}
+
+/// [`crate::cellar`] 的异步版本
+pub async fn cellar() -> Result<String> {
+    Brew::default()
+        .arg("--cellar")
+        .output_async().await
+}
+
+/// [`crate::env()`] 的异步版本
+pub async fn env() -> Result<Env> {
+    let output = Brew::default()
+        .args(["--env", "--plain"])
+        .output_async().await?;
+    Ok(Env::from(&output)?)
+}
+
+/// [`crate::env_shell`] 的异步版本
+pub async fn env_shell() -> Result<String> {
+    Brew::default()
+        .args(["--env", "--shell=auto"])
+        .output_async().await
+}
+
+/// [`crate::update`] 的异步版本
+pub async fn update() -> Result<BrewOutput> {
+    Brew::default()
+        .arg("update")
+        .run_async().await
+}
+
+/// [`crate::update_spawn`] 的异步版本
+pub async fn update_spawn() -> Result<()> {
+    Brew::default()
+        .arg("update")
+        .spawn_async().await
+}
+
+/// [`crate::update_spawn_with`] 的异步版本
+pub async fn update_spawn_with<F>(on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent) + Send
+{
+    Brew::default()
+        .arg("update")
+        .spawn_with_async(on_event).await
+}
+
+/// [`crate::install`] 的异步版本
+pub async fn install(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["install", name])
+        .run_async().await
+}
+
+/// [`crate::install_spawn`] 的异步版本
+pub async fn install_spawn(name: &str) -> Result<()> {
+    Brew::default()
+        .args(["install", name])
+        .spawn_async().await
+}
+
+/// [`crate::install_spawn_with`] 的异步版本
+pub async fn install_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent) + Send
+{
+    Brew::default()
+        .args(["install", name])
+        .spawn_with_async(on_event).await
+}
+
+/// [`crate::install_cask`] 的异步版本
+pub async fn install_cask(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["install", "--cask", name])
+        .run_async().await
+}
+
+/// [`crate::install_cask_spawn`] 的异步版本
+pub async fn install_cask_spawn(name: &str) -> Result<()> {
+    Brew::default()
+        .args(["install", "--cask", name])
+        .spawn_async().await
+}
+
+/// [`crate::reinstall`] 的异步版本
+pub async fn reinstall(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["reinstall", name])
+        .run_async().await
+}
+
+/// [`crate::reinstall_spawn`] 的异步版本
+pub async fn reinstall_spawn(name: &str) -> Result<()> {
+    Brew::default()
+        .args(["reinstall", name])
+        .spawn_async().await
+}
+
+/// [`crate::reinstall_spawn_with`] 的异步版本
+pub async fn reinstall_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent) + Send
+{
+    Brew::default()
+        .args(["reinstall", name])
+        .spawn_with_async(on_event).await
+}
+
+/// [`crate::uninstall`] 的异步版本
+pub async fn uninstall(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["uninstall", name])
+        .run_async().await
+}
+
+/// [`crate::upgrade`] 的异步版本
+pub async fn upgrade(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["upgrade", name])
+        .run_async().await
+}
+
+/// [`crate::upgrade_spawn`] 的异步版本
+pub async fn upgrade_spawn(name: &str) -> Result<()> {
+    Brew::default()
+        .args(["upgrade", name])
+        .spawn_async().await
+}
+
+/// [`crate::upgrade_spawn_with`] 的异步版本
+pub async fn upgrade_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent) + Send
+{
+    Brew::default()
+        .args(["upgrade", name])
+        .spawn_with_async(on_event).await
+}
+
+/// [`crate::services`] 的异步版本
+pub async fn services() -> Result<Vec<Service>> {
+    let out = Brew::default()
+        .args(["services", "--json"])
+        .output_async().await?;
+    let srvs: Vec<Service> = from_json("Service", &out)?;
+    Ok(srvs)
+}
+
+/// [`crate::services_info`] 的异步版本
+pub async fn services_info(name: &str) -> Result<ServiceInfo> {
+    let out = Brew::default()
+        .args(["services", "info", name, "--json"])
+        .output_async().await?;
+    let infos: Vec<ServiceInfo> = from_json("ServiceInfo", &out)?;
+    first_info(name, infos)
+}
+
+/// [`crate::services_start`] 的异步版本
+pub async fn services_start(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "start", name])
+        .run_async().await
+}
+
+/// [`crate::services_run`] 的异步版本
+pub async fn services_run(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "run", name])
+        .run_async().await
+}
+
+/// [`crate::services_stop`] 的异步版本
+pub async fn services_stop(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "stop", name])
+        .run_async().await
+}
+
+/// [`crate::services_restart`] 的异步版本
+pub async fn services_restart(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "restart", name])
+        .run_async().await
+}
+
+/// [`crate::services_kill`] 的异步版本
+pub async fn services_kill(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "kill", name])
+        .run_async().await
+}
+
+/// [`crate::services_cleanup`] 的异步版本
+pub async fn services_cleanup() -> Result<BrewOutput> {
+    Brew::default()
+        .args(["services", "cleanup"])
+        .run_async().await
+}
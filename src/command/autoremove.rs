@@ -0,0 +1,110 @@
+use crate::error::{BrewError, Result};
+
+use crate::{info_installed, Brew, Orphan, RemovedKeg};
+
+/// 执行 `brew leaves [--installed-on-request]` 命令
+///
+/// 返回没有被其他已安装包依赖的 `Formula`，离线计算见 [`crate::Package::leaves`]
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["leaves", "--installed-on-request"], MockResponse::stdout("curl\ntree\nwget\n"))));
+///
+/// fn main() {
+///     let leaves = brew::leaves(true).unwrap();
+///
+///     assert_eq!(leaves, ["curl", "tree", "wget"]);
+/// }
+/// ```
+pub fn leaves(installed_on_request: bool) -> Result<Vec<String>> {
+    let mut brew = Brew::default();
+    brew.arg("leaves");
+    if installed_on_request {
+        brew.arg("--installed-on-request");
+    }
+    brew.output_vec()
+}
+
+/// 查询 `brew autoremove` 会删除哪些包，通过 [`crate::Package::autoremove_plan`] 在本地计算，不会删除任何东西
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json").unwrap())));
+///
+/// fn main() {
+///     for orphan in brew::autoremove_plan().unwrap() {
+///         println!("{orphan}");
+///     }
+/// }
+/// ```
+pub fn autoremove_plan() -> Result<Vec<Orphan>> {
+    Ok(info_installed()?.autoremove_plan())
+}
+
+/// 按确认过的计划删除不再需要的依赖 `brew autoremove` 命令
+///
+/// `plan` 为通过 [`autoremove_plan`] 得到并确认过的结果，执行前会重新计算，
+/// 和 `plan` 中的包不一致时返回 [`BrewError::PlanChanged`]，不会删除任何东西，成功时返回被删除的 keg
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json").unwrap())
+/// #     .on(["autoremove"], MockResponse::stdout("\
+/// # ==> Autoremoving 1 unneeded formula:
+/// # pcre2
+/// # Uninstalling /opt/homebrew/Cellar/pcre2/10.44... (242 files, 6.4MB)
+/// # "))));
+///
+/// use brew::BrewError;
+///
+/// fn main() {
+///     let plan = brew::autoremove_plan().unwrap();
+///     let removed = brew::autoremove(&plan).unwrap();
+///
+///     assert_eq!(removed.len(), 1);
+///     assert_eq!(removed[0].name, "pcre2");
+///     assert_eq!(removed[0].version, "10.44");
+///     assert_eq!(removed[0].files, 242);
+///
+///     // 确认之后又有包可以删除
+///     let err = brew::autoremove(&[]).unwrap_err();
+///     assert!(matches!(err, BrewError::PlanChanged { ref current, .. } if current == &["pcre2"]));
+/// }
+/// ```
+pub fn autoremove(plan: &[Orphan]) -> Result<Vec<RemovedKeg>> {
+    check_plan(plan, &autoremove_plan()?)?;
+    let out = Brew::default()
+        .arg("autoremove")
+        .run()?;
+    Ok(out.stdout.lines().filter_map(RemovedKeg::parse).collect())
+}
+
+/// 确认的计划和当前计算的结果中的包不一致时返回 [`BrewError::PlanChanged`]
+pub(crate) fn check_plan(confirmed: &[Orphan], current: &[Orphan]) -> Result<()> {
+    let names = |plan: &[Orphan]| {
+        let mut names: Vec<String> = plan.iter().map(|o| o.name.clone()).collect();
+        names.sort();
+        names
+    };
+    let (confirmed, current) = (names(confirmed), names(current));
+    if confirmed == current {
+        Ok(())
+    } else {
+        Err(BrewError::PlanChanged { confirmed, current })
+    }
+}
@@ -1,9 +1,19 @@
-use std::{collections::HashMap, process::{Child, Command, Stdio}};
-use std::io::{self, BufRead};
+use std::{collections::{BTreeMap, HashMap}, ffi::{OsStr, OsString}, path::{Path, PathBuf}, process::Command, sync::Arc, thread, time::Duration};
 
-use anyhow::{anyhow, Result};
+use crate::command::output::BrewOutput;
+use crate::env::{default_env, HomebrewEnv};
+use crate::lock::{is_mutating, mutation_lock, MutationLock};
+use crate::metrics::{metrics_observer, MetricsObserver};
+use crate::mirror::MirrorProfile;
+use crate::error::{BrewError, Result};
+use crate::retry::{RetryAttempt, RetryPolicy};
+use crate::trace::CallSpan;
+use crate::executor::{current_executor, detect_prompt, BrewEvent, BrewExecutor, CancelHandle, Invocation, OutputStream, StdinMode};
 
-use crate::config::get_brew_bin;
+/// [`Brew::env_clear`] 后默认保留的环境变量，`brew` 运行时需要用到
+pub const ENV_ALLOWLIST: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TMPDIR", "TERM", "LANG", "LC_ALL", "LC_CTYPE",
+];
 
 /// `brew` 命令构造器
 ///
@@ -13,6 +23,10 @@ use crate::config::get_brew_bin;
 ///
 /// ```
 /// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--caskroom"], MockResponse::stdout("/opt/homebrew/Caskroom\n"))));
 ///
 /// let out = homebrew::Brew::new("--caskroom")
 ///     // .set_env("HOMEBREW_NO_AUTO_UPDATE", "1")
@@ -31,6 +45,10 @@ use crate::config::get_brew_bin;
 ///
 /// ```
 /// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["search", "wget"], MockResponse::stdout("wget\nwget2\nwgetpaste\n"))));
 ///
 /// let out = homebrew::Brew::new("search wget")
 ///     .set_env_no_auto_update()
@@ -45,6 +63,10 @@ use crate::config::get_brew_bin;
 ///
 /// ```
 /// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["search", "wget"], MockResponse::stdout("wget\nwget2\nwgetpaste\n"))));
 ///
 /// let out = homebrew::Brew::default()
 ///     .set_cmd("search wget")
@@ -52,39 +74,127 @@ use crate::config::get_brew_bin;
 ///
 /// assert_eq!(out, ["wget", "wget2", "wgetpaste"]);
 /// ```
+///
+/// Examples
+///
+/// use `arg()` and `args()`
+///
+/// 参数会原样传给 `brew`，不会按空格拆分
+///
+/// ```
+/// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["search", "wget"], MockResponse::stdout("wget\nwget2\nwgetpaste\n"))));
+///
+/// let out = homebrew::Brew::default()
+///     .arg("search")
+///     .args(["wget"])
+///     .output_vec().unwrap();
+///
+/// assert_eq!(out, ["wget", "wget2", "wgetpaste"]);
+/// ```
 #[derive(Debug, Clone)]
 pub struct Brew {
-    cmd_: String,
+    args_: Vec<OsString>,
     env_: HashMap<String, String>,
+    bin_: Option<PathBuf>,
+    executor_: Option<Arc<dyn BrewExecutor>>,
+    timeout_: Option<Duration>,
+    cancel_: Option<CancelHandle>,
+    env_clear_: bool,
+    env_allow_: Vec<String>,
+    env_remove_: Vec<String>,
+    current_dir_: Option<PathBuf>,
+    lock_: Option<MutationLock>,
+    retry_: Option<RetryPolicy>,
+    metrics_: Option<Arc<dyn MetricsObserver>>,
+    stdin_: StdinMode,
+    prompt_detection_: Option<bool>,
 }
 
 impl Default for Brew {
     /// 给一个默认的 `Brew` 会自动设置环境变量等信息
+    ///
+    /// 包括 `HOMEBREW_NO_AUTO_UPDATE=1` 和 [`crate::set_default_env`] 设置的环境变量
     fn default() -> Self{
         let mut e = HashMap::new();
         e.insert("HOMEBREW_NO_AUTO_UPDATE".to_string(), "1".to_string());
+        e.extend(default_env().vars());
         Self {
-            cmd_: String::new(),
+            args_: Vec::new(),
             env_: e,
+            bin_: None,
+            executor_: None,
+            timeout_: None,
+            cancel_: None,
+            env_clear_: false,
+            env_allow_: Vec::new(),
+            env_remove_: Vec::new(),
+            current_dir_: None,
+            lock_: None,
+            retry_: None,
+            metrics_: None,
+            stdin_: StdinMode::Null,
+            prompt_detection_: None,
         }
     }
 }
 
 impl Brew {
-    /// 新建一个 `brew` 命令构造器
+    /// 新建一个 `brew` 命令构造器，会带上 [`crate::set_default_env`] 设置的环境变量
+    ///
+    /// `cmd` 会按空白字符拆分成参数，参数中包含空格时请用 [`Brew::arg`] 或 [`Brew::args`]
     pub fn new<T: AsRef<str>>(cmd: T) -> Self {
         Self {
-            cmd_: cmd.as_ref().to_string(),
-            env_: HashMap::new(),
+            args_: split_cmd(cmd.as_ref()),
+            env_: default_env().vars().into_iter().collect(),
+            bin_: None,
+            executor_: None,
+            timeout_: None,
+            cancel_: None,
+            env_clear_: false,
+            env_allow_: Vec::new(),
+            env_remove_: Vec::new(),
+            current_dir_: None,
+            lock_: None,
+            retry_: None,
+            metrics_: None,
+            stdin_: StdinMode::Null,
+            prompt_detection_: None,
         }
     }
 
-    /// 设置命令
+    /// 设置命令，会替换掉已有的参数
+    ///
+    /// `cmd` 会按空白字符拆分成参数，参数中包含空格时请用 [`Brew::arg`] 或 [`Brew::args`]
     pub fn set_cmd<S: AsRef<str>>(&mut self, cmd: S) -> &mut Self{
-        self.cmd_ = cmd.as_ref().to_string();
+        self.args_ = split_cmd(cmd.as_ref());
+        self
+    }
+
+    /// 追加一个参数
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.args_.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// 追加多个参数
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+        where I: IntoIterator<Item = S>, S: AsRef<OsStr>
+    {
+        for arg in args {
+            self.arg(arg);
+        }
         self
     }
 
+    /// 获取全部参数
+    pub fn get_args(&self) -> &[OsString] {
+        &self.args_
+    }
+
     /// 添加环境变量
     pub fn set_env<K: AsRef<str>, V: AsRef<str>>(&mut self, key: K, value: V) -> &mut Self{
         self.env_.insert(key.as_ref().to_string(), value.as_ref().to_string());
@@ -98,28 +208,273 @@ impl Brew {
         self.set_env("HOMEBREW_NO_AUTO_UPDATE", "1")
     }
 
-    /// 返回 `brew` 命令并得到 [`String`] 类型数据
-    pub fn output(&self) -> Result<String> {
-        let bin = get_brew_bin()?;
-        let cmds = self.cmd_.split(' ');
-        let output = Command::new(bin)
-            .args(cmds)
-            .envs(&self.env_)
-            .output()?;
-
-        let outerr = String::from_utf8_lossy(&output.stderr);
-        if !outerr.is_empty() {
-            if let Some(e) = outerr.strip_suffix("\n") {
-                return Err(anyhow!("{e}"));
-            } else {
-                return Err(anyhow!("{outerr}"));
-            }
+    /// 不继承当前进程的环境变量，只保留 [`ENV_ALLOWLIST`] 和 [`Brew::env_allow`] 中的变量
+    ///
+    /// 通过 [`Brew::set_env`] 设置的变量不受影响
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// let env = homebrew::Brew::new("config")
+    ///     .env_clear()
+    ///     .env_allow("HTTPS_PROXY")
+    ///     .set_env("HOMEBREW_NO_ANALYTICS", "1")
+    ///     .effective_env();
+    ///
+    /// assert_eq!(env["HOMEBREW_NO_ANALYTICS"], "1");
+    /// assert!(env.keys().all(|k| k == "HOMEBREW_NO_ANALYTICS" || k == "HTTPS_PROXY"
+    ///     || homebrew::ENV_ALLOWLIST.contains(&k.as_str())));
+    /// ```
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.env_clear_ = true;
+        for key in ENV_ALLOWLIST {
+            self.env_allow(key);
+        }
+        self
+    }
+
+    /// 清空环境变量时额外保留的变量
+    pub fn env_allow<K: AsRef<str>>(&mut self, key: K) -> &mut Self {
+        let key = key.as_ref().to_string();
+        if !self.env_allow_.contains(&key) {
+            self.env_allow_.push(key);
+        }
+        self
+    }
+
+    /// 不继承当前进程中的某个环境变量，比如开发环境中遗留的 `HOMEBREW_PREFIX`
+    ///
+    /// 同时会移除之前通过 [`Brew::set_env`] 设置的同名变量
+    pub fn env_remove<K: AsRef<str>>(&mut self, key: K) -> &mut Self {
+        let key = key.as_ref().to_string();
+        self.env_.remove(&key);
+        if !self.env_remove_.contains(&key) {
+            self.env_remove_.push(key);
+        }
+        self
+    }
+
+    /// 设置 `sudo` 的密码程序，通过环境变量 `SUDO_ASKPASS` 传给 `brew`
+    ///
+    /// `brew` 检测到 `SUDO_ASKPASS` 后会以 `sudo -A` 执行需要提权的操作，比如安装 `.pkg` 包，
+    /// 没有终端时不再等待输入密码，程序需要把密码输出到 stdout
+    ///
+    /// 安装前是否需要提权可以通过 [`crate::Cask::install_sudo_reasons`] 判断
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// let env = homebrew::Brew::new("install --cask zoom")
+    ///     .set_askpass("/usr/local/bin/askpass")
+    ///     .effective_env();
+    ///
+    /// assert_eq!(env["SUDO_ASKPASS"], "/usr/local/bin/askpass");
+    /// ```
+    pub fn set_askpass<P: AsRef<Path>>(&mut self, program: P) -> &mut Self {
+        self.set_env("SUDO_ASKPASS", program.as_ref().to_string_lossy())
+    }
+
+    /// 设置 `brew` 的工作目录
+    pub fn set_current_dir<P: Into<PathBuf>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir_ = Some(dir.into());
+        self
+    }
+
+    /// 执行时实际生效的环境变量，用于调试
+    pub fn effective_env(&self) -> BTreeMap<String, String> {
+        self.invocation().effective_env()
+    }
+
+    /// 添加 [`HomebrewEnv`] 中设置的环境变量
+    pub fn set_homebrew_env(&mut self, env: &HomebrewEnv) -> &mut Self {
+        self.env_.extend(env.vars());
+        self
+    }
+
+    /// 使用指定的镜像，见 [`MirrorProfile`]
+    pub fn set_mirror(&mut self, mirror: &MirrorProfile) -> &mut Self {
+        self.set_homebrew_env(&mirror.to_env())
+    }
+
+    /// 指定 `brew` 可执行文件路径，不设置时通过 [`crate::get_brew_bin`] 查找
+    pub fn set_bin<P: Into<PathBuf>>(&mut self, bin: P) -> &mut Self {
+        self.bin_ = Some(bin.into());
+        self
+    }
+
+    /// 设置执行器，不设置时使用 [`crate::current_executor`]
+    pub fn set_executor(&mut self, executor: Arc<dyn BrewExecutor>) -> &mut Self {
+        self.executor_ = Some(executor);
+        self
+    }
+
+    /// 设置超时时间，超时后 `brew` 进程组会被终止并返回 [`BrewError::Timeout`]
+    ///
+    /// Examples
+    ///
+    /// ```ignore
+    /// use std::time::Duration;
+    ///
+    /// let err = homebrew::Brew::new("update")
+    ///     .set_timeout(Duration::from_secs(60))
+    ///     .run().unwrap_err();
+    ///
+    /// if let homebrew::BrewError::Timeout { stdout, .. } = err {
+    ///     println!("{stdout}");
+    /// }
+    /// ```
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout_ = Some(timeout);
+        self
+    }
+
+    /// 设置取消句柄，在其他线程调用 [`CancelHandle::cancel`] 后返回 [`BrewError::Cancelled`]
+    pub fn set_cancel_handle(&mut self, handle: &CancelHandle) -> &mut Self {
+        self.cancel_ = Some(handle.clone());
+        self
+    }
+
+    /// 设置文件锁，命令会修改系统时先拿到锁再执行，不设置时使用 [`crate::set_mutation_lock`] 设置的全局锁
+    pub fn set_lock(&mut self, lock: MutationLock) -> &mut Self {
+        self.lock_ = Some(lock);
+        self
+    }
+
+    /// 设置重试策略，下载失败、`git` 拉取失败等网络问题会按 [`RetryPolicy`] 重试
+    ///
+    /// [`Brew::run`] 返回的 [`BrewOutput::attempts`] 中记录了之前失败的每一次执行，
+    /// [`Brew::spawn_with`] 重试时回调会再次收到完整的输出
+    pub fn set_retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_ = Some(policy);
+        self
+    }
+
+    /// 设置统计信息的观察者，不设置时使用 [`crate::set_metrics_observer`] 设置的全局观察者
+    pub fn set_metrics_observer(&mut self, observer: Arc<dyn MetricsObserver>) -> &mut Self {
+        self.metrics_ = Some(observer);
+        self
+    }
+
+    /// 设置 `brew` 的标准输入，默认为 [`StdinMode::Null`]，需要在终端中回答提示时使用 [`StdinMode::Inherit`]
+    pub fn set_stdin(&mut self, mode: StdinMode) -> &mut Self {
+        self.stdin_ = mode;
+        self
+    }
+
+    /// 执行 [`Brew::spawn`] 时是否检测等待输入的提示，检测到后终止命令并返回 [`BrewError::InteractivePromptDetected`]
+    ///
+    /// 默认在标准输入为 [`StdinMode::Null`] 时开启，没有换行符的提示在输出停顿后同样会被检测到，
+    /// 开启检测不会让 `brew` 运行在独立的进程组中，终端的 Ctrl-C 仍然会传给 `brew`
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use homebrew::{MockExecutor, MockResponse};
+    /// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+    /// #     .on(["cleanup", "--prune=all"], MockResponse::stdout("This operation will free approximately 1.2GB of disk space.\nDo you want to continue? [y/N]\n"))));
+    /// use homebrew::BrewError;
+    ///
+    /// let err = homebrew::Brew::new("cleanup --prune=all").spawn_with(|_| {}).unwrap_err();
+    ///
+    /// assert!(matches!(err, BrewError::InteractivePromptDetected { ref prompt } if prompt == "Do you want to continue? [y/N]"));
+    /// ```
+    pub fn set_prompt_detection(&mut self, enabled: bool) -> &mut Self {
+        self.prompt_detection_ = Some(enabled);
+        self
+    }
+
+    /// 生成交给执行器的 [`Invocation`]
+    pub fn invocation(&self) -> Invocation {
+        Invocation {
+            program: self.bin_.clone(),
+            args: self.args_.clone(),
+            envs: self.env_.clone(),
+            timeout: self.timeout_,
+            cancel: self.cancel_.clone(),
+            env_clear: self.env_clear_,
+            env_allow: self.env_allow_.clone(),
+            env_remove: self.env_remove_.clone(),
+            current_dir: self.current_dir_.clone(),
+            stdin: self.stdin_,
         }
-        let out = String::from_utf8_lossy(&output.stdout);
-        if let Some(o) = out.strip_suffix("\n") {
-            return Ok(o.to_string());
+    }
+
+    /// 生成将要执行的 [`Command`]，没有通过 [`Brew::set_bin`] 指定路径时会查找 `brew`
+    ///
+    /// 超时和取消句柄只在执行器中生效，不会体现在 [`Command`] 上
+    pub fn to_command(&self) -> Result<Command> {
+        self.invocation().to_command()
+    }
+
+    /// 渲染成可以粘贴到 shell 中执行的命令，包括设置的环境变量，见 [`Invocation`] 的 `Display`
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// let cmd = homebrew::Brew::default()
+    ///     .args(["install", "--cask", "visual studio code"])
+    ///     .to_shell_string();
+    ///
+    /// assert_eq!(cmd, "HOMEBREW_NO_AUTO_UPDATE=1 brew install --cask 'visual studio code'");
+    /// ```
+    pub fn to_shell_string(&self) -> String {
+        self.invocation().to_string()
+    }
+
+    /// 执行 `brew` 命令并得到完整的 [`BrewOutput`]
+    ///
+    /// 只有退出码不为 0 时才会返回错误，`stderr` 中的警告会保留在结果中
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// extern crate homebrew;
+    /// # use std::sync::Arc;
+    /// # use homebrew::{MockExecutor, MockResponse};
+    /// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+    /// #     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))));
+    ///
+    /// let out = homebrew::Brew::new("--prefix")
+    ///     .set_env_no_auto_update()
+    ///     .run().unwrap();
+    ///
+    /// assert_eq!(out.code, Some(0));
+    /// assert_eq!(out.stdout, "/opt/homebrew");
+    /// assert!(out.warnings.is_empty());
+    /// ```
+    pub fn run(&self) -> Result<BrewOutput> {
+        let executor = self.executor();
+        let inv = self.invocation();
+        let lock = self.lock(&executor);
+        let span = CallSpan::new(&inv, self.metrics_observer(&executor));
+        let _guard = lock.as_ref().map(MutationLock::acquire).transpose().inspect_err(|e| span.failed(e, 0))?;
+        let mut attempts = Vec::new();
+        loop {
+            let res = span.in_scope(|| executor.output(&inv).and_then(|o| {
+                span.exited(o.code, o.stdout.len(), o.stderr.len());
+                BrewOutput::from_exec(&o).into_result()
+            }));
+            match res {
+                Ok(mut out) => {
+                    span.succeeded(attempts.len() as u32 + 1);
+                    out.attempts = attempts;
+                    return Ok(out);
+                }
+                Err(e) => match self.next_retry(&lock, &span, &mut attempts, &e) {
+                    Some(delay) => thread::sleep(delay),
+                    None => {
+                        span.failed(&e, attempts.len() as u32 + 1);
+                        return Err(e);
+                    }
+                },
+            }
         }
-        Ok(out.to_string())
+    }
+
+    /// 返回 `brew` 命令并得到 [`String`] 类型数据
+    pub fn output(&self) -> Result<String> {
+        Ok(self.run()?.stdout)
     }
 
     /// 返回 `brew` 命令并得到 [`Vec<String>`] 数据
@@ -134,6 +489,10 @@ impl Brew {
 
     /// 实时打印命令信息，但是不会返回结果
     ///
+    /// 标准输出打印到 stdout，标准错误打印到 stderr，退出码不为 0 时根据标准错误解析出错误
+    ///
+    /// 需要自己处理输出时请用 [`Brew::spawn_with`]
+    ///
     /// Examples
     ///
     /// ```ignore
@@ -148,29 +507,324 @@ impl Brew {
     /// Already up-to-date.
     /// ```
     pub fn spawn(&self) -> Result<()> {
-        let bin = get_brew_bin()?;
-        let cmds = self.cmd_.split(' ');
-        let mut child: Child = Command::new(bin)
-            .args(cmds)
-            .stdout(Stdio::piped()) // 将标准输出设置为管道
-            .spawn()?; // 启动命令
+        let mut stderr = String::new();
+        // 逐行读取输出并打印
+        let code = self.spawn_with(|event| print_event(event, &mut stderr))?;
+        check_code(code, &stderr)
+    }
+
+    /// 执行命令并把每一行输出交给回调，返回退出码
+    ///
+    /// 退出码不为 0 时不会返回错误，失败原因可以从 [`OutputStream::Stderr`] 的输出中获取
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use homebrew::{MockExecutor, MockResponse};
+    /// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+    /// #     .on(["update"], MockResponse::stdout("==> Updating Homebrew...\nAlready up-to-date.\n")
+    /// #         .with_stderr("Warning: mirror is slow\n"))));
+    /// use homebrew::{BrewEvent, OutputStream};
+    ///
+    /// let mut events: Vec<BrewEvent> = Vec::new();
+    /// let code = homebrew::Brew::default()
+    ///     .arg("update")
+    ///     .spawn_with(|event| events.push(event.clone()))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(code, Some(0));
+    /// assert_eq!(events[0], BrewEvent::stdout("==> Updating Homebrew..."));
+    /// assert!(events.iter().any(|e| e.stream == OutputStream::Stderr));
+    /// ```
+    pub fn spawn_with<F>(&self, mut on_event: F) -> Result<Option<i32>>
+        where F: FnMut(&BrewEvent)
+    {
+        let executor = self.executor();
+        let mut inv = self.invocation();
+        let prompt_cancel = self.prompt_cancel(&mut inv);
+        let lock = self.lock(&executor);
+        let span = CallSpan::new(&inv, self.metrics_observer(&executor));
+        let _guard = lock.as_ref().map(MutationLock::acquire).transpose().inspect_err(|e| span.failed(e, 0))?;
+        let mut attempts = Vec::new();
+        loop {
+            let mut stderr = String::new();
+            let mut stdout_bytes = 0;
+            let mut prompt = None;
+            let res = span.in_scope(|| executor.spawn(&inv, &mut |event| {
+                watch_prompt(event, &prompt_cancel, &mut prompt);
+                count_stdout(event, &mut stdout_bytes);
+                record_stderr(event, &mut stderr);
+                on_event(event);
+            }));
+            if let Ok(code) = res {
+                span.exited(code, stdout_bytes, stderr.len());
+            }
+            if let Some(prompt) = prompt {
+                let err = BrewError::InteractivePromptDetected { prompt };
+                span.failed(&err, attempts.len() as u32 + 1);
+                return Err(err);
+            }
+            let classified;
+            let err = match &res {
+                Ok(Some(0)) => {
+                    span.succeeded(attempts.len() as u32 + 1);
+                    return res;
+                }
+                Ok(code) => {
+                    classified = BrewError::classify(*code, &stderr);
+                    &classified
+                }
+                Err(e) => e,
+            };
+            match self.next_retry(&lock, &span, &mut attempts, err) {
+                Some(delay) => thread::sleep(delay),
+                None => {
+                    span.failed(err, attempts.len() as u32 + 1);
+                    return res;
+                }
+            }
+        }
+    }
+
+    /// [`Brew::run`] 的异步版本，需要开启 `async` feature
+    ///
+    /// 使用 `tokio::process` 执行，`Future` 被取消时会杀掉 `brew` 进程
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use homebrew::{MockExecutor, MockResponse};
+    /// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+    /// #     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))));
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let out = homebrew::Brew::default()
+    ///     .arg("--prefix")
+    ///     .run_async().await.unwrap();
+    ///
+    /// assert_eq!(out.stdout, "/opt/homebrew");
+    /// # })
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn run_async(&self) -> Result<BrewOutput> {
+        let executor = self.executor();
+        let inv = self.invocation();
+        let lock = self.lock(&executor);
+        let span = CallSpan::new(&inv, self.metrics_observer(&executor));
+        let _guard = match &lock {
+            Some(l) => Some(l.acquire_async().await.inspect_err(|e| span.failed(e, 0))?),
+            None => None,
+        };
+        let mut attempts = Vec::new();
+        loop {
+            let res = span.instrument(executor.output_async(&inv)).await.and_then(|o| {
+                span.in_scope(|| {
+                    span.exited(o.code, o.stdout.len(), o.stderr.len());
+                    BrewOutput::from_exec(&o).into_result()
+                })
+            });
+            match res {
+                Ok(mut out) => {
+                    span.succeeded(attempts.len() as u32 + 1);
+                    out.attempts = attempts;
+                    return Ok(out);
+                }
+                Err(e) => match self.next_retry(&lock, &span, &mut attempts, &e) {
+                    Some(delay) => tokio::time::sleep(delay).await,
+                    None => {
+                        span.failed(&e, attempts.len() as u32 + 1);
+                        return Err(e);
+                    }
+                },
+            }
+        }
+    }
 
-        // 获取标准输出的句柄
-        let stdout = child.stdout.take().ok_or_else(|| anyhow!("Could not capture standard output"))?;
+    /// [`Brew::output`] 的异步版本，需要开启 `async` feature
+    #[cfg(feature = "async")]
+    pub async fn output_async(&self) -> Result<String> {
+        Ok(self.run_async().await?.stdout)
+    }
 
-        // 创建一个 BufReader 来逐行读取输出
-        let reader = io::BufReader::new(stdout);
+    /// [`Brew::output_vec`] 的异步版本，需要开启 `async` feature
+    #[cfg(feature = "async")]
+    pub async fn output_vec_async(&self) -> Result<Vec<String>> {
+        let out = self.output_async().await?;
+        let res: Vec<String> = out.split('\n')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect();
+        Ok(res)
+    }
 
-        // 逐行读取输出并打印
-        for line in reader.lines() {
-            let line = line?;
-            println!("{}", line);
+    /// [`Brew::spawn`] 的异步版本，需要开启 `async` feature
+    ///
+    /// `Future` 被取消时会杀掉 `brew` 进程
+    #[cfg(feature = "async")]
+    pub async fn spawn_async(&self) -> Result<()> {
+        let mut stderr = String::new();
+        let code = self.spawn_with_async(|event| print_event(event, &mut stderr)).await?;
+        check_code(code, &stderr)
+    }
+
+    /// [`Brew::spawn_with`] 的异步版本，需要开启 `async` feature
+    #[cfg(feature = "async")]
+    pub async fn spawn_with_async<F>(&self, mut on_event: F) -> Result<Option<i32>>
+        where F: FnMut(&BrewEvent) + Send
+    {
+        let executor = self.executor();
+        let mut inv = self.invocation();
+        let prompt_cancel = self.prompt_cancel(&mut inv);
+        let lock = self.lock(&executor);
+        let span = CallSpan::new(&inv, self.metrics_observer(&executor));
+        let _guard = match &lock {
+            Some(l) => Some(l.acquire_async().await.inspect_err(|e| span.failed(e, 0))?),
+            None => None,
+        };
+        let mut attempts = Vec::new();
+        loop {
+            let mut stderr = String::new();
+            let mut stdout_bytes = 0;
+            let mut prompt = None;
+            let res = span.instrument(executor.spawn_async(&inv, &mut |event: &BrewEvent| {
+                watch_prompt(event, &prompt_cancel, &mut prompt);
+                count_stdout(event, &mut stdout_bytes);
+                record_stderr(event, &mut stderr);
+                on_event(event);
+            })).await;
+            if let Ok(code) = res {
+                span.exited(code, stdout_bytes, stderr.len());
+            }
+            if let Some(prompt) = prompt {
+                let err = BrewError::InteractivePromptDetected { prompt };
+                span.failed(&err, attempts.len() as u32 + 1);
+                return Err(err);
+            }
+            let classified;
+            let err = match &res {
+                Ok(Some(0)) => {
+                    span.succeeded(attempts.len() as u32 + 1);
+                    return res;
+                }
+                Ok(code) => {
+                    classified = BrewError::classify(*code, &stderr);
+                    &classified
+                }
+                Err(e) => e,
+            };
+            match self.next_retry(&lock, &span, &mut attempts, err) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => {
+                    span.failed(err, attempts.len() as u32 + 1);
+                    return res;
+                }
+            }
+        }
+    }
+
+    /// 命令会修改系统时，获取生效的文件锁，dry run 时不需要
+    fn lock(&self, executor: &Arc<dyn BrewExecutor>) -> Option<MutationLock> {
+        if !is_mutating(&self.args_) || executor.is_dry_run() {
+            return None;
+        }
+        self.lock_.clone().or_else(mutation_lock)
+    }
+
+    /// 执行失败后是否重试，可以重试时记录这次失败并返回等待时间
+    ///
+    /// `brew` 报告 [`BrewError::Locked`] 时优先按文件锁的 `retries` 重试，其他错误按 [`RetryPolicy`] 重试
+    fn next_retry(
+        &self,
+        lock: &Option<MutationLock>,
+        span: &CallSpan,
+        attempts: &mut Vec<RetryAttempt>,
+        err: &BrewError,
+    ) -> Option<Duration> {
+        let attempt = attempts.len() as u32 + 1;
+        let delay = lock.as_ref()
+            .filter(|l| matches!(err, BrewError::Locked { .. }) && attempt <= l.retries)
+            .map(|l| l.retry_interval)
+            .or_else(|| self.retry_.as_ref().and_then(|p| p.delay(attempt, err)))?;
+        let retry = RetryAttempt { attempt, error: err.to_string(), delay };
+        span.retry(&retry);
+        attempts.push(retry);
+        Some(delay)
+    }
+
+    /// 开启提示检测时，给调用设置一个检测到提示后用来终止命令的取消句柄，会跟随用户设置的句柄
+    fn prompt_cancel(&self, inv: &mut Invocation) -> Option<CancelHandle> {
+        if !self.prompt_detection_.unwrap_or(self.stdin_ == StdinMode::Null) {
+            return None;
+        }
+        let handle = inv.cancel.as_ref().map(CancelHandle::child).unwrap_or_else(CancelHandle::internal);
+        inv.cancel = Some(handle.clone());
+        Some(handle)
+    }
+
+    /// 获取生效的观察者，dry run 时不记录
+    fn metrics_observer(&self, executor: &Arc<dyn BrewExecutor>) -> Option<Arc<dyn MetricsObserver>> {
+        if executor.is_dry_run() {
+            return None;
+        }
+        self.metrics_.clone().or_else(metrics_observer)
+    }
+
+    /// 获取生效的执行器
+    fn executor(&self) -> Arc<dyn BrewExecutor> {
+        match &self.executor_ {
+            Some(e) => e.clone(),
+            None => current_executor(),
+        }
+    }
+}
+
+/// 打印一行输出，同时记录标准错误用于解析错误
+fn print_event(event: &BrewEvent, stderr: &mut String) {
+    match event.stream {
+        OutputStream::Stdout => println!("{}", event.line),
+        OutputStream::Stderr => eprintln!("{}", event.line),
+    }
+    record_stderr(event, stderr);
+}
+
+/// 检查一行输出是否为等待输入的提示，第一次检测到时终止命令
+fn watch_prompt(event: &BrewEvent, cancel: &Option<CancelHandle>, prompt: &mut Option<String>) {
+    let Some(cancel) = cancel else { return };
+    if prompt.is_none() {
+        if let Some(p) = detect_prompt(&event.line) {
+            *prompt = Some(p);
+            cancel.cancel();
         }
+    }
+}
+
+/// 统计标准输出的字节数，包括换行
+fn count_stdout(event: &BrewEvent, bytes: &mut usize) {
+    if event.stream == OutputStream::Stdout {
+        *bytes += event.line.len() + 1;
+    }
+}
+
+/// 记录标准错误的内容
+fn record_stderr(event: &BrewEvent, stderr: &mut String) {
+    if event.stream == OutputStream::Stderr {
+        stderr.push_str(&event.line);
+        stderr.push('\n');
+    }
+}
 
-        // 等待命令完成
-        let _ = child.wait()?;
-        Ok(())
+/// 退出码不为 0 时根据标准错误解析出错误
+fn check_code(code: Option<i32>, stderr: &str) -> Result<()> {
+    if code != Some(0) {
+        return Err(BrewError::classify(code, stderr));
     }
+    Ok(())
+}
+
+/// 兼容字符串形式的命令，按空白字符拆分
+fn split_cmd(cmd: &str) -> Vec<OsString> {
+    cmd.split_whitespace().map(OsString::from).collect()
 }
 
 /// 执行 `brew` 命令
@@ -183,6 +837,10 @@ impl Brew {
 ///
 /// ```
 /// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))));
 ///
 /// let out = homebrew::brew("--prefix").unwrap();
 ///
@@ -190,11 +848,36 @@ impl Brew {
 /// assert_eq!(out, "/opt/homebrew");
 /// ```
 pub fn brew(cmd: &str) -> Result<String> {
-    Brew::new(cmd)
-        .set_env_no_auto_update()
+    Brew::default()
+        .set_cmd(cmd)
         .output()
 }
 
+/// 执行 `brew` 命令并得到完整的 [`BrewOutput`]
+///
+/// 默认添加环境变量 HOMEBREW_NO_AUTO_UPDATE=1
+///
+/// 和 [`brew`] 不同的是，命令成功时 `stderr` 中的警告不会被丢弃
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))));
+///
+/// let out = homebrew::brew_run("--prefix").unwrap();
+///
+/// assert_eq!(out.stdout, "/opt/homebrew");
+/// ```
+pub fn brew_run(cmd: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .set_cmd(cmd)
+        .run()
+}
+
 /// 执行 `brew` 命令并实时输出信息
 ///
 /// 默认添加环境变量 HOMEBREW_NO_AUTO_UPDATE=1
@@ -215,7 +898,95 @@ pub fn brew(cmd: &str) -> Result<String> {
 /// Already up-to-date.
 /// ```
 pub fn brew_spawn(cmd: &str) -> Result<()> {
-    Brew::new(cmd)
-        .set_env_no_auto_update()
+    Brew::default()
+        .set_cmd(cmd)
         .spawn()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args() {
+        let b = Brew::new(" info  wget --json=v2");
+        assert_eq!(b.get_args(), ["info", "wget", "--json=v2"]);
+
+        let mut b = Brew::default();
+        b.arg("tap").args(["user/repo", "https://example.com/my repo.git"]);
+        assert_eq!(b.get_args(), ["tap", "user/repo", "https://example.com/my repo.git"]);
+
+        b.set_cmd("list --cask");
+        assert_eq!(b.get_args(), ["list", "--cask"]);
+    }
+
+    #[test]
+    fn test_lock_retry() {
+        use crate::{MockExecutor, MockResponse, MutationLock, ReplayExecutor};
+
+        let locked = "Error: A `brew install wget` process has already locked /opt/homebrew/Cellar/wget.\n";
+        let lock = MutationLock::new("target/lock-test/retry.lock")
+            .retries(2)
+            .retry_interval(Duration::from_millis(10));
+        let mock = Arc::new(MockExecutor::new().on(["install", "wget"], MockResponse::failure(1, locked)));
+        let err = Brew::default()
+            .args(["install", "wget"])
+            .set_executor(mock.clone())
+            .set_lock(lock.clone())
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, BrewError::Locked { .. }));
+        assert_eq!(mock.calls().len(), 3);
+
+        let text = format!(
+            "{}\n{}\n",
+            serde_json::json!({"args": ["install", "wget"], "env": {}, "stdout": "", "stderr": locked, "exit_code": 1, "duration_ms": 1}),
+            serde_json::json!({"args": ["install", "wget"], "env": {}, "stdout": "installed\n", "stderr": "", "exit_code": 0, "duration_ms": 1}),
+        );
+        let replay: ReplayExecutor = text.parse().unwrap();
+        let mut lines = Vec::new();
+        let code = Brew::default()
+            .args(["install", "wget"])
+            .set_executor(Arc::new(replay))
+            .set_lock(lock)
+            .spawn_with(|e| lines.push(e.line.clone()))
+            .unwrap();
+        assert_eq!(code, Some(0));
+        assert_eq!(lines.last().unwrap(), "installed");
+    }
+
+    #[test]
+    fn test_network_retry() {
+        use crate::{InMemoryMetrics, MockExecutor, MockResponse, RetryClass, RetryPolicy};
+
+        let curl = "curl: (28) Operation timed out after 30001 milliseconds\nError: wget: Failed to download resource \"wget\"\n";
+        let mock = Arc::new(MockExecutor::new().on(["install", "wget"], MockResponse::failure(1, curl)));
+        let policy = RetryPolicy::new(3).initial_backoff(Duration::from_millis(5));
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let err = Brew::default()
+            .args(["install", "wget"])
+            .set_executor(mock.clone())
+            .set_retry(policy.clone())
+            .set_metrics_observer(metrics.clone())
+            .run()
+            .unwrap_err();
+        assert!(matches!(err, BrewError::CurlFailed { code: 28, .. }));
+        assert_eq!(mock.calls().len(), 3);
+        let records = metrics.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].attempts, records[0].code, records[0].error), (3, Some(1), Some("curl_failed")));
+
+        // 不在 retry_on 中的错误不会重试
+        let mock = Arc::new(MockExecutor::new().on(["install", "wget"], MockResponse::failure(1, curl)));
+        let mut lines = Vec::new();
+        let code = Brew::default()
+            .args(["install", "wget"])
+            .set_executor(mock.clone())
+            .set_retry(policy.retry_on([RetryClass::Git]))
+            .spawn_with(|e| lines.push(e.line.clone()))
+            .unwrap();
+        assert_eq!(code, Some(1));
+        assert_eq!(mock.calls().len(), 1);
+        assert_eq!(lines.len(), 2);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::Result;
 use crate::{brew, Config, Env};
 
 /// 执行 `brew config` 命令并得到结构体
@@ -6,14 +7,18 @@ use crate::{brew, Config, Env};
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["config"], MockResponse::stdout_file("examples/data/config.txt").unwrap())));
 ///
 /// let config = homebrew::config().unwrap();
 /// assert_eq!(config.origin, "https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/brew.git");
 /// assert_eq!(config.prefix, "/opt/homebrew");
 /// ```
-pub fn config() -> anyhow::Result<Config> {
+pub fn config() -> Result<Config> {
     let output = brew("config")?;
-    Config::from(&output)
+    Ok(Config::from(&output)?)
 }
 
 /// 执行 `brew --cache` 命令
@@ -22,11 +27,15 @@ pub fn config() -> anyhow::Result<Config> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--cache"], MockResponse::stdout("/Users/wxnacy/Library/Caches/Homebrew\n"))));
 ///
 /// let out = homebrew::cache().unwrap();
 /// assert_eq!(out, "/Users/wxnacy/Library/Caches/Homebrew");
 /// ```
-pub fn cache() -> anyhow::Result<String> {
+pub fn cache() -> Result<String> {
     brew("--cache")
 }
 
@@ -36,11 +45,15 @@ pub fn cache() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--version"], MockResponse::stdout("Homebrew 4.4.15-70-g1e91082\n"))));
 ///
 /// let out = homebrew::version().unwrap();
 /// assert_eq!(out, "Homebrew 4.4.15-70-g1e91082");
 /// ```
-pub fn version() -> anyhow::Result<String> {
+pub fn version() -> Result<String> {
     brew("--version")
 }
 
@@ -50,11 +63,15 @@ pub fn version() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--repository"], MockResponse::stdout("/opt/homebrew\n"))));
 ///
 /// let out = homebrew::repository().unwrap();
 /// assert_eq!(out, "/opt/homebrew");
 /// ```
-pub fn repository() -> anyhow::Result<String> {
+pub fn repository() -> Result<String> {
     brew("--repository")
 }
 
@@ -64,11 +81,15 @@ pub fn repository() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))));
 ///
 /// let out = homebrew::prefix().unwrap();
 /// assert_eq!(out, "/opt/homebrew");
 /// ```
-pub fn prefix() -> anyhow::Result<String> {
+pub fn prefix() -> Result<String> {
     brew("--prefix")
 }
 
@@ -78,11 +99,15 @@ pub fn prefix() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--caskroom"], MockResponse::stdout("/opt/homebrew/Caskroom\n"))));
 ///
 /// let out = homebrew::caskroom().unwrap();
 /// assert_eq!(out, "/opt/homebrew/Caskroom");
 /// ```
-pub fn caskroom() -> anyhow::Result<String> {
+pub fn caskroom() -> Result<String> {
     brew("--caskroom")
 }
 
@@ -92,11 +117,15 @@ pub fn caskroom() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--cellar"], MockResponse::stdout("/opt/homebrew/Cellar\n"))));
 ///
 /// let out = homebrew::cellar().unwrap();
 /// assert_eq!(out, "/opt/homebrew/Cellar");
 /// ```
-pub fn cellar() -> anyhow::Result<String> {
+pub fn cellar() -> Result<String> {
     brew("--cellar")
 }
 
@@ -106,14 +135,18 @@ pub fn cellar() -> anyhow::Result<String> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--env", "--plain"], MockResponse::stdout_file("examples/data/env.txt").unwrap())));
 ///
 /// let config = homebrew::env().unwrap();
 /// assert_eq!(config.cmake_prefix_path, "/opt/homebrew");
 /// assert_eq!(config.git, "git");
 /// ```
-pub fn env() -> anyhow::Result<Env> {
+pub fn env() -> Result<Env> {
     let output = brew("--env --plain")?;
-    Env::from(&output)
+    Ok(Env::from(&output)?)
 }
 
 /// 执行 `brew --env --shell=auto` 命令并得到 shell 文本
@@ -122,10 +155,14 @@ pub fn env() -> anyhow::Result<Env> {
 ///
 /// ```
 /// use homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["--env", "--shell=auto"], MockResponse::stdout("export HOMEBREW_CC=\"clang\";\n"))));
 ///
 /// let config = homebrew::env_shell().unwrap();
 /// println!("{config}");
 /// ```
-pub fn env_shell() -> anyhow::Result<String> {
+pub fn env_shell() -> Result<String> {
     brew("--env --shell=auto")
 }
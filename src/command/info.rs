@@ -1,6 +1,6 @@
-use anyhow::Result;
+use crate::error::Result;
 
-use crate::{brew, Package};
+use crate::{Brew, Package};
 
 /// 执行 `brew info {name} --json=v2` 命令
 ///
@@ -10,6 +10,11 @@ use crate::{brew, Package};
 ///
 /// ```
 /// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["info", "rust", "--json=v2"], MockResponse::stdout_file("examples/data/rust.json").unwrap())
+/// #     .on(["info", "kitty", "--json=v2"], MockResponse::stdout_file("examples/data/kitty.json").unwrap())));
 ///
 /// fn main() {
 ///     let pkg = brew::info("rust").unwrap();
@@ -32,7 +37,9 @@ use crate::{brew, Package};
 /// }
 /// ```
 pub fn info(name: &str) -> Result<Package> {
-    let out = brew(format!("info {name} --json=v2").as_str())?;
+    let out = Brew::default()
+        .args(["info", name, "--json=v2"])
+        .output()?;
     let pkg = Package::from(&out)?;
     Ok(pkg)
 }
@@ -54,7 +61,37 @@ pub fn info(name: &str) -> Result<Package> {
 /// }
 /// ```
 pub fn info_all() -> Result<Package> {
-    let out = brew("info --eval-all --json=v2")?;
+    let out = Brew::default()
+        .args(["info", "--eval-all", "--json=v2"])
+        .output()?;
+    let pkg = Package::from_all(&out)?;
+    Ok(pkg)
+}
+
+/// 执行 `brew info --installed --json=v2` 命令
+///
+/// 获取全部已安装的包，包括 `formula` 和 `cask`
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json").unwrap())));
+///
+/// fn main() {
+///     let pkg = brew::info_installed().unwrap();
+///
+///     assert!(pkg.formulae().iter().all(|f| f.is_installed()));
+///     assert!(pkg.casks().iter().all(|c| c.is_installed()));
+/// }
+/// ```
+pub fn info_installed() -> Result<Package> {
+    let out = Brew::default()
+        .args(["info", "--installed", "--json=v2"])
+        .output()?;
     let pkg = Package::from_all(&out)?;
     Ok(pkg)
 }
@@ -1,5 +1,5 @@
-use crate::{brew, brew_spawn};
-use anyhow::Result;
+use crate::{Brew, BrewEvent, BrewOutput};
+use crate::error::Result;
 
 
 
@@ -15,8 +15,10 @@ use anyhow::Result;
 /// let out = homebrew::update().unwrap();
 /// println!("{out}");
 /// ```
-pub fn update() -> Result<String> {
-    brew("update")
+pub fn update() -> Result<BrewOutput> {
+    Brew::default()
+        .arg("update")
+        .run()
 }
 
 /// 执行更新 `brew update` 命令并实时输出
@@ -31,7 +33,31 @@ pub fn update() -> Result<String> {
 /// homebrew::update_spawn().unwrap();
 /// ```
 pub fn update_spawn() -> Result<()> {
-    brew_spawn("update")
+    Brew::default()
+        .arg("update")
+        .spawn()
+}
+
+/// 执行 `brew update` 命令，并把每一行输出交给回调
+///
+/// 返回退出码，退出码不为 0 时不会返回错误，详见 [`Brew::spawn_with`]
+///
+/// Examples
+///
+/// ```ignore
+/// use homebrew::OutputStream;
+///
+/// let code = homebrew::update_spawn_with(|event| match event.stream {
+///     OutputStream::Stdout => println!("{}", event.line),
+///     OutputStream::Stderr => eprintln!("{}", event.line),
+/// }).unwrap();
+/// ```
+pub fn update_spawn_with<F>(on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent)
+{
+    Brew::default()
+        .arg("update")
+        .spawn_with(on_event)
 }
 
 /// 安装软件 `brew install [name]` 命令
@@ -45,8 +71,10 @@ pub fn update_spawn() -> Result<()> {
 ///
 /// homebrew::install("btop").unwrap();
 /// ```
-pub fn install(name: &str) -> Result<String> {
-    brew(format!("install {name}").as_str())
+pub fn install(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["install", name])
+        .run()
 }
 
 /// 安装软件 `brew install [name]` 命令，并实时输出
@@ -61,7 +89,39 @@ pub fn install(name: &str) -> Result<String> {
 /// homebrew::install_spawn("btop").unwrap();
 /// ```
 pub fn install_spawn(name: &str) -> Result<()> {
-    brew_spawn(format!("install {name}").as_str())
+    Brew::default()
+        .args(["install", name])
+        .spawn()
+}
+
+/// 执行 `brew install [name]` 命令，并把每一行输出交给回调
+///
+/// 返回退出码，退出码不为 0 时不会返回错误，详见 [`Brew::spawn_with`]
+///
+/// Examples
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["install", "btop"], MockResponse::stdout("==> Fetching btop\n==> Pouring btop--1.4.0.arm64_sequoia.bottle.tar.gz\n"))));
+/// use homebrew::OutputStream;
+///
+/// let mut lines = Vec::new();
+/// let code = homebrew::install_spawn_with("btop", |event| match event.stream {
+///     OutputStream::Stdout => lines.push(event.line.clone()),
+///     OutputStream::Stderr => eprintln!("{}", event.line),
+/// }).unwrap();
+///
+/// assert_eq!(code, Some(0));
+/// assert_eq!(lines[0], "==> Fetching btop");
+/// ```
+pub fn install_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent)
+{
+    Brew::default()
+        .args(["install", name])
+        .spawn_with(on_event)
 }
 
 /// 安装 `Cask` 软件 `brew install --cask [name]` 命令
@@ -75,8 +135,10 @@ pub fn install_spawn(name: &str) -> Result<()> {
 ///
 /// homebrew::install_cask("kitty").unwrap();
 /// ```
-pub fn install_cask(name: &str) -> Result<String> {
-    brew(format!("install --cask {name}").as_str())
+pub fn install_cask(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["install", "--cask", name])
+        .run()
 }
 
 /// 安装 `Cask` 软件 `brew install --cask [name]` 命令，并实时输出
@@ -91,7 +153,9 @@ pub fn install_cask(name: &str) -> Result<String> {
 /// homebrew::install_cask_spawn("kitty").unwrap();
 /// ```
 pub fn install_cask_spawn(name: &str) -> Result<()> {
-    brew_spawn(format!("install --cask {name}").as_str())
+    Brew::default()
+        .args(["install", "--cask", name])
+        .spawn()
 }
 
 /// 重新安装软件 `brew reinstall [name]` 命令
@@ -105,8 +169,10 @@ pub fn install_cask_spawn(name: &str) -> Result<()> {
 ///
 /// homebrew::reinstall("btop").unwrap();
 /// ```
-pub fn reinstall(name: &str) -> Result<String> {
-    brew(format!("reinstall {name}").as_str())
+pub fn reinstall(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["reinstall", name])
+        .run()
 }
 
 /// 重新安装软件 `brew reinstall [name]` 命令，并实时输出
@@ -121,7 +187,31 @@ pub fn reinstall(name: &str) -> Result<String> {
 /// homebrew::reinstall_spawn("btop").unwrap();
 /// ```
 pub fn reinstall_spawn(name: &str) -> Result<()> {
-    brew_spawn(format!("reinstall {name}").as_str())
+    Brew::default()
+        .args(["reinstall", name])
+        .spawn()
+}
+
+/// 执行 `brew reinstall [name]` 命令，并把每一行输出交给回调
+///
+/// 返回退出码，退出码不为 0 时不会返回错误，详见 [`Brew::spawn_with`]
+///
+/// Examples
+///
+/// ```ignore
+/// use homebrew::OutputStream;
+///
+/// let code = homebrew::reinstall_spawn_with("btop", |event| match event.stream {
+///     OutputStream::Stdout => println!("{}", event.line),
+///     OutputStream::Stderr => eprintln!("{}", event.line),
+/// }).unwrap();
+/// ```
+pub fn reinstall_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent)
+{
+    Brew::default()
+        .args(["reinstall", name])
+        .spawn_with(on_event)
 }
 
 /// 卸载软件 `brew uninstall [name]` 命令
@@ -130,13 +220,21 @@ pub fn reinstall_spawn(name: &str) -> Result<()> {
 ///
 /// ```
 /// use homebrew;
+/// use homebrew::BrewError;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["uninstall", "gotop"], MockResponse::failure(1, "Error: No such keg: /opt/homebrew/Cellar/gotop\n"))));
 ///
 /// let out = homebrew::uninstall("gotop").unwrap_err();
 ///
+/// assert!(matches!(out, BrewError::NoSuchKeg { .. }));
 /// assert_eq!(out.to_string(), "Error: No such keg: /opt/homebrew/Cellar/gotop");
 /// ```
-pub fn uninstall(name: &str) -> Result<String> {
-    brew(format!("uninstall {name}").as_str())
+pub fn uninstall(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["uninstall", name])
+        .run()
 }
 
 /// 升级软件 `brew upgrade [name]` 命令
@@ -150,8 +248,10 @@ pub fn uninstall(name: &str) -> Result<String> {
 ///
 /// homebrew::upgrade("btop").unwrap();
 /// ```
-pub fn upgrade(name: &str) -> Result<String> {
-    brew(format!("upgrade {name}").as_str())
+pub fn upgrade(name: &str) -> Result<BrewOutput> {
+    Brew::default()
+        .args(["upgrade", name])
+        .run()
 }
 
 /// 升级软件 `brew upgrade [name]` 命令，并实时输出
@@ -166,5 +266,29 @@ pub fn upgrade(name: &str) -> Result<String> {
 /// homebrew::upgrade_spawn("btop").unwrap();
 /// ```
 pub fn upgrade_spawn(name: &str) -> Result<()> {
-    brew_spawn(format!("upgrade {name}").as_str())
+    Brew::default()
+        .args(["upgrade", name])
+        .spawn()
+}
+
+/// 执行 `brew upgrade [name]` 命令，并把每一行输出交给回调
+///
+/// 返回退出码，退出码不为 0 时不会返回错误，详见 [`Brew::spawn_with`]
+///
+/// Examples
+///
+/// ```ignore
+/// use homebrew::OutputStream;
+///
+/// let code = homebrew::upgrade_spawn_with("btop", |event| match event.stream {
+///     OutputStream::Stdout => println!("{}", event.line),
+///     OutputStream::Stderr => eprintln!("{}", event.line),
+/// }).unwrap();
+/// ```
+pub fn upgrade_spawn_with<F>(name: &str, on_event: F) -> Result<Option<i32>>
+    where F: FnMut(&BrewEvent)
+{
+    Brew::default()
+        .args(["upgrade", name])
+        .spawn_with(on_event)
 }
@@ -1,5 +1,5 @@
 
-use anyhow::Result;
+use crate::error::Result;
 
 use crate::Brew;
 
@@ -27,13 +27,29 @@ pub fn list_formulae() -> Result<Vec<String>> {
 #[cfg(test)]
 mod tests {
 
+    use std::sync::Arc;
+
+    use crate::{with_executor, MockExecutor, MockResponse};
+
     use super::*;
     #[test]
-    fn test_list() -> anyhow::Result<()> {
-        let all_pkgs = list()?;
-        let cask_pkgs = list_cask()?;
-        let formulae_pkgs = list_formulae()?;
-        assert_eq!(all_pkgs.len(), cask_pkgs.len() + formulae_pkgs.len());
-        Ok(())
+    fn test_list() -> Result<()> {
+        let mock = MockExecutor::new()
+            .on(["list"], MockResponse::stdout("rust
+wget
+kitty
+"))
+            .on(["list", "--cask"], MockResponse::stdout("kitty
+"))
+            .on(["list", "--formula"], MockResponse::stdout("rust
+wget
+"));
+        with_executor(Arc::new(mock), || {
+            let all_pkgs = list()?;
+            let cask_pkgs = list_cask()?;
+            let formulae_pkgs = list_formulae()?;
+            assert_eq!(all_pkgs.len(), cask_pkgs.len() + formulae_pkgs.len());
+            Ok(())
+        })
     }
 }
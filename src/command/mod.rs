@@ -1,7 +1,14 @@
 pub mod list;
 pub mod cmd;
+pub mod output;
 pub mod config;
 pub mod info;
+pub mod autoremove;
+pub mod outdated;
 pub mod search;
+pub mod uses;
 pub mod service;
 pub mod install;
+pub mod progress;
+#[cfg(feature = "async")]
+pub mod asynchronous;
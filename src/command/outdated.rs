@@ -0,0 +1,65 @@
+use crate::error::Result;
+
+use crate::{Brew, Outdated};
+
+/// 执行 `brew outdated --json=v2 [--greedy]` 命令
+///
+/// 返回可以升级的包，`greedy` 为 `true` 时包括 `auto_updates` 和 `latest` 的 `Cask`，
+/// 离线计算见 [`crate::Package::outdated`]
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["outdated", "--json=v2", "--greedy"], MockResponse::stdout_file("examples/data/outdated.json").unwrap())));
+///
+/// fn main() {
+///     let outdated = brew::outdated(true).unwrap();
+///
+///     for o in outdated.iter().filter(|o| !o.pinned) {
+///         println!("{} {} -> {}", o.name, o.installed_versions[0], o.current_version);
+///     }
+///     assert_eq!(outdated[0].name, "curl");
+///     assert_eq!(outdated[0].current_version.as_str(), "8.11.1");
+/// }
+/// ```
+pub fn outdated(greedy: bool) -> Result<Vec<Outdated>> {
+    let mut brew = Brew::default();
+    brew.args(["outdated", "--json=v2"]);
+    if greedy {
+        brew.arg("--greedy");
+    }
+    let out = brew.output()?;
+    Ok(Outdated::from(&out)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use crate::{with_executor, MockExecutor, MockResponse, Package};
+
+    use super::*;
+
+    #[test]
+    fn test_outdated() -> Result<()> {
+        let json_str = fs::read_to_string("examples/data/outdated.json")?;
+        let mut value: serde_json::Value = serde_json::from_str(&json_str)?;
+        value["casks"].as_array_mut().unwrap().retain(|c| c["name"] != "rustdesk");
+        let mock = MockExecutor::new()
+            .on(["outdated", "--json=v2", "--greedy"], MockResponse::stdout(json_str))
+            .on(["outdated", "--json=v2"], MockResponse::stdout(value.to_string()));
+
+        let pkg = Package::from_all(&fs::read_to_string("examples/data/installed.json")?)?;
+        // `BrewVersion` 相等时版本号的写法可能不同，比如 `1.0` 和 `1.0.0`，序列化后比较原始的版本号
+        let raw = |outdated: Vec<Outdated>| serde_json::to_value(outdated).unwrap();
+        with_executor(Arc::new(mock), || {
+            assert_eq!(raw(outdated(true)?), raw(pkg.outdated(true)));
+            assert_eq!(raw(outdated(false)?), raw(pkg.outdated(false)));
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use crate::{BrewError, ExecOutput, RetryAttempt};
+
+/// `brew` 命令执行完成后的结果
+///
+/// 是否成功只由退出码决定，`stderr` 中的 `Warning:` 和 `Error:` 会被分别解析出来
+///
+/// Examples
+///
+/// ```
+/// use homebrew::BrewOutput;
+///
+/// let stderr = "Warning: wget 1.25.0 is already installed and up-to-date.\nTo reinstall 1.25.0, run:\n  brew reinstall wget\n";
+/// let out = BrewOutput::new(Some(0), "", stderr);
+///
+/// assert!(out.success());
+/// assert_eq!(out.warnings, [
+///     "wget 1.25.0 is already installed and up-to-date.\nTo reinstall 1.25.0, run:\n  brew reinstall wget",
+/// ]);
+/// assert!(out.errors.is_empty());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BrewOutput {
+    /// 退出码，被信号终止时为 `None`
+    pub code: Option<i32>,
+
+    /// 标准输出，去掉了末尾的换行
+    pub stdout: String,
+
+    /// 原始的标准错误输出
+    pub stderr: String,
+
+    /// `Warning:` 开头的信息，不包含前缀
+    pub warnings: Vec<String>,
+
+    /// `Error:` 开头的信息，不包含前缀
+    pub errors: Vec<String>,
+
+    /// 通过 [`crate::Brew::set_retry`] 重试时，之前失败的每一次执行
+    pub attempts: Vec<RetryAttempt>,
+}
+
+impl BrewOutput {
+    /// 通过退出码和输出内容新建结构体
+    pub fn new(code: Option<i32>, stdout: &str, stderr: &str) -> Self {
+        let (warnings, errors) = parse_stderr(stderr);
+        Self {
+            code,
+            stdout: stdout.strip_suffix('\n').unwrap_or(stdout).to_string(),
+            stderr: stderr.to_string(),
+            warnings,
+            errors,
+            attempts: Vec::new(),
+        }
+    }
+
+    /// 通过执行器的原始输出新建结构体
+    pub fn from_exec(output: &ExecOutput) -> Self {
+        Self::new(
+            output.code,
+            &String::from_utf8_lossy(&output.stdout),
+            &String::from_utf8_lossy(&output.stderr),
+        )
+    }
+
+    /// 退出码是否为 0
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// 将可以识别的警告转换为 [`BrewError`]，比如 [`BrewError::AlreadyInstalled`]
+    pub fn typed_warnings(&self) -> Vec<BrewError> {
+        self.warnings.iter()
+            .map(|w| BrewError::classify(self.code, &format!("Warning: {w}")))
+            .filter(|e| !matches!(e, BrewError::NonZeroExit { .. }))
+            .collect()
+    }
+
+    /// 成功时返回自身，否则根据输出解析出错误
+    pub fn into_result(self) -> crate::Result<Self> {
+        if self.success() {
+            Ok(self)
+        } else {
+            Err(BrewError::classify(self.code, &self.stderr))
+        }
+    }
+}
+
+impl fmt::Display for BrewOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.stdout)
+    }
+}
+
+/// 从 `stderr` 中拆分出警告和错误，不带前缀的行视为上一条信息的延续
+fn parse_stderr(stderr: &str) -> (Vec<String>, Vec<String>) {
+    let mut warnings: Vec<String> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    // 记录上一条信息是否为警告
+    let mut last: Option<bool> = None;
+    for line in stderr.lines() {
+        if let Some(w) = line.strip_prefix("Warning: ") {
+            warnings.push(w.to_string());
+            last = Some(true);
+        } else if let Some(e) = line.strip_prefix("Error: ") {
+            errors.push(e.to_string());
+            last = Some(false);
+        } else if !line.is_empty() {
+            let msg = match last {
+                Some(true) => warnings.last_mut(),
+                Some(false) => errors.last_mut(),
+                None => None,
+            };
+            if let Some(msg) = msg {
+                msg.push('\n');
+                msg.push_str(line);
+            }
+        }
+    }
+    (warnings, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stderr() {
+        let stderr = "Warning: Treating wget as a formula.\nError: Unknown command: foo\nsee `brew help`\n";
+        let out = BrewOutput::new(Some(1), "", stderr);
+
+        assert!(!out.success());
+        assert_eq!(out.warnings, ["Treating wget as a formula."]);
+        assert_eq!(out.errors, ["Unknown command: foo\nsee `brew help`"]);
+        assert!(matches!(out.into_result(), Err(BrewError::NonZeroExit { code: Some(1), .. })));
+    }
+
+    #[test]
+    fn test_typed_warnings() {
+        let out = BrewOutput::new(Some(0), "", "Warning: Service `mpd` is not started.\n");
+
+        assert!(out.clone().into_result().is_ok());
+        assert!(matches!(
+            out.typed_warnings().as_slice(),
+            [BrewError::ServiceNotStarted { name }] if name == "mpd"
+        ));
+    }
+}
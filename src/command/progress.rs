@@ -0,0 +1,369 @@
+use std::path::PathBuf;
+
+/// 安装、升级过程中 `brew` 输出的阶段信息
+///
+/// 由 [`InstallParser`] 从实时输出中解析得到
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallEvent {
+    /// `==> Fetching [name]`
+    FetchStarted {
+        name: String,
+    },
+
+    /// `==> Fetching dependencies for [name]: [a], [b]`
+    FetchDependencies {
+        name: String,
+        dependencies: Vec<String>,
+    },
+
+    /// `==> Downloading [url]`
+    DownloadStarted {
+        url: String,
+    },
+
+    /// 下载进度条，如 `###########   45.3%`
+    DownloadProgress {
+        percent: f32,
+    },
+
+    /// `Already downloaded: [path]`
+    AlreadyDownloaded {
+        path: PathBuf,
+    },
+
+    /// `==> Installing [name]`
+    Installing {
+        name: String,
+    },
+
+    /// `==> Upgrading [name]`
+    Upgrading {
+        name: String,
+    },
+
+    /// `==> Pouring [bottle]`
+    Pouring {
+        bottle: String,
+    },
+
+    /// `==> Linking ...`，`message` 为 `Linking` 之后的内容
+    Linking {
+        message: String,
+    },
+
+    /// `==> Caveats` 之后的提示信息，同时安装多个软件时 `name` 为对应的软件名
+    Caveats {
+        name: Option<String>,
+        text: String,
+    },
+
+    /// `🍺  /opt/homebrew/Cellar/gotop/4.2.0: 7 files, 10.7MB`
+    Summary {
+        keg: PathBuf,
+        files: u64,
+        /// 原始的大小文本，如 `10.7MB`
+        size: String,
+        /// 从源码编译时的耗时，如 `2 minutes 3 seconds`
+        built_in: Option<String>,
+    },
+
+    /// `🍺  kitty was successfully installed!`
+    CaskInstalled {
+        name: String,
+    },
+
+    /// ``==> Running `brew cleanup [name]`...``
+    Cleanup {
+        name: String,
+    },
+
+    /// 其他 `==>` 开头的信息，不包含前缀
+    Step {
+        title: String,
+    },
+}
+
+impl InstallEvent {
+    /// [`InstallEvent::Summary`] 的软件名，从 `keg` 路径中解析
+    pub fn summary_name(&self) -> Option<&str> {
+        match self {
+            InstallEvent::Summary { keg, .. } => keg.parent()?.file_name()?.to_str(),
+            _ => None,
+        }
+    }
+
+    /// [`InstallEvent::Summary`] 的大小，按 `brew` 的规则以 1024 为单位换算成字节
+    pub fn summary_bytes(&self) -> Option<u64> {
+        match self {
+            InstallEvent::Summary { size, .. } => parse_size(size),
+            _ => None,
+        }
+    }
+}
+
+/// 解析 `brew install` 和 `brew upgrade` 的实时输出
+///
+/// `Caveats` 会跨越多行，所以解析器是有状态的，所有行都交给 [`InstallParser::push`]，
+/// 结束后调用 [`InstallParser::finish`] 取出最后的 `Caveats`
+///
+/// Examples
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["install", "gotop"], MockResponse::stdout("\
+/// # ==> Fetching gotop
+/// # ==> Downloading https://ghcr.io/v2/homebrew/core/gotop/blobs/sha256:44c51f80
+/// # ==> Pouring gotop-4.2.0.arm64_sequoia.bottle.tar.gz
+/// # 🍺  /opt/homebrew/Cellar/gotop/4.2.0: 7 files, 10.7MB
+/// # "))));
+/// use homebrew::{InstallEvent, InstallParser};
+///
+/// let mut parser = InstallParser::new();
+/// let mut events = Vec::new();
+/// homebrew::install_spawn_with("gotop", |e| events.extend(parser.push(&e.line))).unwrap();
+/// events.extend(parser.finish());
+///
+/// assert_eq!(events[0], InstallEvent::FetchStarted { name: "gotop".to_string() });
+/// assert_eq!(events.last().unwrap().summary_name(), Some("gotop"));
+/// assert_eq!(events.last().unwrap().summary_bytes(), Some(11219763));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct InstallParser {
+    /// 正在收集的 `Caveats`
+    caveats: Option<(Option<String>, Vec<String>)>,
+}
+
+impl InstallParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析一行输出，无法识别的行返回空
+    pub fn push(&mut self, line: &str) -> Vec<InstallEvent> {
+        let mut events = Vec::new();
+        // 进度条通过 `\r` 刷新，一行中可能有多个进度
+        for part in line.split('\r') {
+            self.push_part(part, &mut events);
+        }
+        events
+    }
+
+    /// 输出结束，返回还没有结束的 `Caveats`
+    pub fn finish(&mut self) -> Vec<InstallEvent> {
+        self.take_caveats().into_iter().collect()
+    }
+
+    fn push_part(&mut self, line: &str, events: &mut Vec<InstallEvent>) {
+        let trimmed = line.trim_end();
+        if let Some(title) = trimmed.strip_prefix("==> ") {
+            let title = title.trim();
+            if title == "Caveats" {
+                events.extend(self.take_caveats());
+                self.caveats = Some((None, Vec::new()));
+                return;
+            }
+            // 多个软件的 `Caveats` 之间以软件名分隔
+            if title != "Summary" && is_name(title) {
+                if let Some((name, lines)) = self.caveats.as_mut() {
+                    if name.is_none() && lines.is_empty() {
+                        *name = Some(title.to_string());
+                    } else {
+                        events.extend(self.take_caveats());
+                        self.caveats = Some((Some(title.to_string()), Vec::new()));
+                    }
+                    return;
+                }
+            }
+            events.extend(self.take_caveats());
+            if title != "Summary" {
+                events.push(parse_step(title));
+            }
+            return;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("🍺") {
+            events.extend(self.take_caveats());
+            events.extend(parse_beer(rest.trim()));
+            return;
+        }
+
+        if let Some((_, lines)) = self.caveats.as_mut() {
+            lines.push(trimmed.to_string());
+            return;
+        }
+
+        if let Some(path) = trimmed.strip_prefix("Already downloaded: ") {
+            events.push(InstallEvent::AlreadyDownloaded { path: PathBuf::from(path) });
+        } else if let Some(percent) = parse_progress(trimmed) {
+            events.push(InstallEvent::DownloadProgress { percent });
+        }
+    }
+
+    fn take_caveats(&mut self) -> Option<InstallEvent> {
+        let (name, lines) = self.caveats.take()?;
+        let text = lines.join("\n").trim_end().to_string();
+        Some(InstallEvent::Caveats { name, text })
+    }
+}
+
+/// 解析 `==>` 之后的内容
+fn parse_step(title: &str) -> InstallEvent {
+    if let Some(rest) = title.strip_prefix("Fetching dependencies for ") {
+        if let Some((name, deps)) = rest.split_once(": ") {
+            return InstallEvent::FetchDependencies { name: name.to_string(), dependencies: split_names(deps) };
+        }
+    }
+    if let Some(name) = title.strip_prefix("Fetching ") {
+        return InstallEvent::FetchStarted { name: name.to_string() };
+    }
+    if let Some(url) = title.strip_prefix("Downloading ") {
+        return InstallEvent::DownloadStarted { url: url.to_string() };
+    }
+    if let Some(bottle) = title.strip_prefix("Pouring ") {
+        return InstallEvent::Pouring { bottle: bottle.to_string() };
+    }
+    if let Some(message) = title.strip_prefix("Linking ") {
+        return InstallEvent::Linking { message: message.to_string() };
+    }
+    if let Some(rest) = title.strip_prefix("Running `brew cleanup ") {
+        let name = rest.split('`').next().unwrap_or(rest);
+        return InstallEvent::Cleanup { name: name.to_string() };
+    }
+    if let Some(rest) = title.strip_prefix("Installing ") {
+        // `Installing wget dependency: libunistring`
+        if let Some((_, dep)) = rest.split_once(" dependency: ") {
+            return InstallEvent::Installing { name: dep.to_string() };
+        }
+        // `Installing dependencies for wget: a, b` 之类的汇总信息不是具体的软件
+        if is_name(rest) {
+            return InstallEvent::Installing { name: rest.to_string() };
+        }
+    }
+    if let Some(name) = title.strip_prefix("Upgrading ").filter(|n| is_name(n)) {
+        return InstallEvent::Upgrading { name: name.to_string() };
+    }
+    InstallEvent::Step { title: title.to_string() }
+}
+
+/// 解析 `🍺` 之后的内容
+fn parse_beer(rest: &str) -> Option<InstallEvent> {
+    if let Some(name) = rest.strip_suffix(" was successfully installed!") {
+        return Some(InstallEvent::CaskInstalled { name: name.to_string() });
+    }
+    let (keg, stats) = rest.rsplit_once(": ")?;
+    let mut parts = stats.split(", ");
+    let count = parts.next()?;
+    let files = count.strip_suffix(" files").or_else(|| count.strip_suffix(" file"))?.parse().ok()?;
+    let size = parts.next()?.to_string();
+    let built_in = parts.next().and_then(|p| p.strip_prefix("built in ")).map(String::from);
+    Some(InstallEvent::Summary { keg: PathBuf::from(keg), files, size, built_in })
+}
+
+/// 解析 `curl` 的进度条，如 `#####    12.5%`
+fn parse_progress(line: &str) -> Option<f32> {
+    let line = line.trim();
+    let percent = line.strip_suffix('%')?;
+    let (bar, num) = percent.rsplit_once(' ').unwrap_or(("", percent));
+    if !bar.trim().chars().all(|c| matches!(c, '#' | '=' | '-' | 'O' | 'o')) {
+        return None;
+    }
+    num.parse().ok()
+}
+
+/// 按 `brew` 的单位换算，如 `10.7MB`、`512B`
+pub(crate) fn parse_size(size: &str) -> Option<u64> {
+    let units = [("GB", 1u64 << 30), ("MB", 1 << 20), ("KB", 1 << 10), ("B", 1)];
+    for (unit, factor) in units {
+        if let Some(num) = size.strip_suffix(unit) {
+            let num: f64 = num.trim().parse().ok()?;
+            return Some((num * factor as f64) as u64);
+        }
+    }
+    None
+}
+
+/// 是否为单个软件名，如 `gotop`、`python@3.13`、`homebrew/cask/kitty`
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && !s.contains(char::is_whitespace)
+}
+
+fn split_names(s: &str) -> Vec<String> {
+    s.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_install() {
+        let output = "\
+==> Fetching dependencies for wget: libunistring, gettext
+==> Fetching libunistring
+==> Downloading https://ghcr.io/v2/homebrew/core/libunistring/blobs/sha256:4b4b0e
+##################                                                        25.0%\r####################################################################### 100.0%
+==> Fetching wget
+Already downloaded: /Users/wxnacy/Library/Caches/Homebrew/downloads/44c51f80--wget-1.25.0.arm64_sequoia.bottle.tar.gz
+==> Installing dependencies for wget: libunistring, gettext
+==> Installing wget dependency: libunistring
+==> Pouring libunistring--1.3.arm64_sequoia.bottle.tar.gz
+🍺  /opt/homebrew/Cellar/libunistring/1.3: 59 files, 5.4MB
+==> Installing wget
+==> Pouring wget--1.25.0.arm64_sequoia.bottle.tar.gz
+==> Caveats
+==> wget
+wget has been built with a custom CA bundle.
+
+See https://example.com for details.
+==> Summary
+🍺  /opt/homebrew/Cellar/wget/1.25.0: 92 files, 4.5MB, built in 1 minute 2 seconds
+==> Running `brew cleanup wget`...
+";
+        let mut parser = InstallParser::new();
+        let mut events: Vec<InstallEvent> = output.lines().flat_map(|l| parser.push(l)).collect();
+        events.extend(parser.finish());
+
+        assert_eq!(events[0], InstallEvent::FetchDependencies {
+            name: "wget".to_string(),
+            dependencies: vec!["libunistring".to_string(), "gettext".to_string()],
+        });
+        assert_eq!(events[3], InstallEvent::DownloadProgress { percent: 25.0 });
+        assert_eq!(events[4], InstallEvent::DownloadProgress { percent: 100.0 });
+        assert!(matches!(events[6], InstallEvent::AlreadyDownloaded { .. }));
+        assert!(matches!(events[7], InstallEvent::Step { .. }));
+        assert_eq!(events[8], InstallEvent::Installing { name: "libunistring".to_string() });
+        assert_eq!(events[9], InstallEvent::Pouring { bottle: "libunistring--1.3.arm64_sequoia.bottle.tar.gz".to_string() });
+        assert_eq!(events[10].summary_name(), Some("libunistring"));
+        assert_eq!(events[11], InstallEvent::Installing { name: "wget".to_string() });
+        assert_eq!(events[13], InstallEvent::Caveats {
+            name: Some("wget".to_string()),
+            text: "wget has been built with a custom CA bundle.\n\nSee https://example.com for details.".to_string(),
+        });
+        assert_eq!(events[14], InstallEvent::Summary {
+            keg: PathBuf::from("/opt/homebrew/Cellar/wget/1.25.0"),
+            files: 92,
+            size: "4.5MB".to_string(),
+            built_in: Some("1 minute 2 seconds".to_string()),
+        });
+        assert_eq!(events[15], InstallEvent::Cleanup { name: "wget".to_string() });
+        assert_eq!(events.len(), 16);
+    }
+
+    #[test]
+    fn test_parse_cask() {
+        let mut parser = InstallParser::new();
+        let events: Vec<InstallEvent> = [
+            "==> Linking Binary 'kitty' to '/opt/homebrew/bin/kitty'",
+            "🍺  kitty was successfully installed!",
+            "==> Caveats",
+            "kitty needs Rosetta 2",
+        ].iter().flat_map(|l| parser.push(l)).collect();
+        assert_eq!(events, [
+            InstallEvent::Linking { message: "Binary 'kitty' to '/opt/homebrew/bin/kitty'".to_string() },
+            InstallEvent::CaskInstalled { name: "kitty".to_string() },
+        ]);
+        assert_eq!(parser.finish(), [InstallEvent::Caveats { name: None, text: "kitty needs Rosetta 2".to_string() }]);
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use crate::error::Result;
 
 use crate::Brew;
 
@@ -9,6 +9,10 @@ use crate::Brew;
 ///
 /// ```
 /// extern crate homebrew;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["search", "wget"], MockResponse::stdout("wget\nwget2\nwgetpaste\n"))));
 ///
 /// let out = homebrew::search("wget")
 ///     .unwrap();
@@ -17,6 +21,6 @@ use crate::Brew;
 /// ```
 pub fn search(name: &str) -> Result<Vec<String>> {
     Brew::default()
-        .set_cmd(format!("search {name}"))
+        .args(["search", name])
         .output_vec()
 }
@@ -1,4 +1,6 @@
-use crate::{brew, Service, ServiceInfo};
+use crate::error::{BrewError, Result};
+use crate::trace::from_json;
+use crate::{Brew, BrewOutput, Service, ServiceInfo};
 
 
 /// 列出所有服务，就像运行 `brew services`
@@ -8,6 +10,10 @@ use crate::{brew, Service, ServiceInfo};
 /// ```
 /// use homebrew;
 /// use homebrew::ServiceStatus;
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["services", "--json"], MockResponse::stdout_file("examples/data/services.json").unwrap())));
 ///
 /// let srvs = homebrew::services().unwrap();
 ///
@@ -19,9 +25,11 @@ use crate::{brew, Service, ServiceInfo};
 /// assert_eq!(srv.file, "/opt/homebrew/opt/unbound/homebrew.mxcl.unbound.plist");
 /// assert_eq!(srv.exit_code, None);
 /// ```
-pub fn services() -> anyhow::Result<Vec<Service>>{
-    let out = brew("services --json")?;
-    let srvs: Vec<Service> = serde_json::from_str(&out)?;
+pub fn services() -> Result<Vec<Service>>{
+    let out = Brew::default()
+        .args(["services", "--json"])
+        .output()?;
+    let srvs: Vec<Service> = from_json("Service", &out)?;
     Ok(srvs)
 }
 
@@ -31,7 +39,13 @@ pub fn services() -> anyhow::Result<Vec<Service>>{
 ///
 /// ```
 /// use homebrew;
-/// use homebrew::ServiceStatus;
+/// use homebrew::{BrewError, ServiceStatus};
+/// # use std::sync::Arc;
+/// # use homebrew::{MockExecutor, MockResponse};
+/// # homebrew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["services", "info", "unbound", "--json"], MockResponse::stdout_file("examples/data/unbound.service.json").unwrap())
+/// #     .on(["services", "info", "ss", "--json"], MockResponse::failure(1, "Error: No available formula with the name \"ss\".\n"))
+/// #     .on(["services", "info", "empty", "--json"], MockResponse::stdout("[]\n"))));
 ///
 /// let info = homebrew::services_info("unbound").unwrap();
 ///
@@ -44,12 +58,27 @@ pub fn services() -> anyhow::Result<Vec<Service>>{
 ///
 /// let info = homebrew::services_info("ss").unwrap_err();
 ///
-/// assert_eq!(info.to_string(), "Error: No available formula with the name \"ss\".")
+/// assert!(matches!(info, BrewError::FormulaUnavailable { ref name } if name == "ss"));
+/// assert_eq!(info.to_string(), "Error: No available formula with the name \"ss\".");
+///
+/// // `brew` 没有返回任何服务
+/// let err = homebrew::services_info("empty").unwrap_err();
+///
+/// assert!(matches!(err, BrewError::Parse(_)));
 /// ```
-pub fn services_info(name: &str) -> anyhow::Result<ServiceInfo>{
-    let out = brew(format!("services info {name} --json").as_str())?;
-    let infos: Vec<ServiceInfo> = serde_json::from_str(&out)?;
-    Ok(infos[0].clone())
+pub fn services_info(name: &str) -> Result<ServiceInfo>{
+    let out = Brew::default()
+        .args(["services", "info", name, "--json"])
+        .output()?;
+    let infos: Vec<ServiceInfo> = from_json("ServiceInfo", &out)?;
+    first_info(name, infos)
+}
+
+/// 取出 `brew services info` 返回的第一项，返回空数组时为 [`BrewError::Parse`]
+pub(crate) fn first_info(name: &str, infos: Vec<ServiceInfo>) -> Result<ServiceInfo> {
+    infos.into_iter()
+        .next()
+        .ok_or_else(|| BrewError::Parse(format!("brew services info {name} returned no service")))
 }
 
 /// 启动服务并注册自启动，就像运行 `brew services start [name]`
@@ -63,17 +92,19 @@ pub fn services_info(name: &str) -> anyhow::Result<ServiceInfo>{
 /// let name = "mpd";
 /// homebrew::services_stop(&name).unwrap();
 /// let info = homebrew::services_start(&name).unwrap();
-/// assert_eq!(info, "==> Successfully started `mpd` (label: homebrew.mxcl.mpd)");
+/// assert_eq!(info.stdout, "==> Successfully started `mpd` (label: homebrew.mxcl.mpd)");
 ///
 /// let info = homebrew::services_start(&name).unwrap();
-/// assert_eq!(info, "Service `mpd` already started, use `brew services restart mpd` to restart.");
+/// assert_eq!(info.stdout, "Service `mpd` already started, use `brew services restart mpd` to restart.");
 ///
 /// let info = homebrew::services_start("ss").unwrap_err();
 ///
 /// assert_eq!(info.to_string(), "Error: No available formula with the name \"ss\".")
 /// ```
-pub fn services_start(name: &str) -> anyhow::Result<String>{
-    brew(format!("services start {name}").as_str())
+pub fn services_start(name: &str) -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "start", name])
+        .run()
 }
 
 /// 启动服务但不注册自启动，就像运行 `brew services run [name]`
@@ -87,17 +118,19 @@ pub fn services_start(name: &str) -> anyhow::Result<String>{
 /// let name = "mpd";
 /// homebrew::services_stop(&name).unwrap();
 /// let info = homebrew::services_run(&name).unwrap();
-/// assert_eq!(info, "==> Successfully ran `mpd` (label: homebrew.mxcl.mpd)");
+/// assert_eq!(info.stdout, "==> Successfully ran `mpd` (label: homebrew.mxcl.mpd)");
 ///
 /// let info = homebrew::services_run(&name).unwrap();
-/// assert_eq!(info, "Service `mpd` already running, use `brew services restart mpd` to restart.");
+/// assert_eq!(info.stdout, "Service `mpd` already running, use `brew services restart mpd` to restart.");
 ///
 /// let info = homebrew::services_run("ss").unwrap_err();
 ///
 /// assert_eq!(info.to_string(), "Error: No available formula with the name \"ss\".")
 /// ```
-pub fn services_run(name: &str) -> anyhow::Result<String>{
-    brew(format!("services run {name}").as_str())
+pub fn services_run(name: &str) -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "run", name])
+        .run()
 }
 
 /// 停止服务并注销自启动，就像运行 `brew services stop [name]`
@@ -106,22 +139,25 @@ pub fn services_run(name: &str) -> anyhow::Result<String>{
 ///
 /// ```ignore
 /// use homebrew;
-/// use homebrew::ServiceStatus;
+/// use homebrew::BrewError;
 ///
 /// // 确保你的机器上已经安装 mpd
 /// homebrew::services_start("mpd").unwrap();
 /// let info = homebrew::services_stop("mpd").unwrap();
-/// assert_eq!(info, "Stopping `mpd`... (might take a while)\n==> Successfully stopped `mpd` (label: homebrew.mxcl.mpd)");
+/// assert_eq!(info.stdout, "Stopping `mpd`... (might take a while)\n==> Successfully stopped `mpd` (label: homebrew.mxcl.mpd)");
 ///
-/// let info = homebrew::services_stop("mpd").unwrap_err();
-/// assert_eq!(info.to_string(), "Warning: Service `mpd` is not started.");
+/// // 服务未启动时只是警告，不会返回错误
+/// let info = homebrew::services_stop("mpd").unwrap();
+/// assert!(matches!(info.typed_warnings()[0], BrewError::ServiceNotStarted { .. }));
 ///
 /// let info = homebrew::services_stop("ss").unwrap_err();
 ///
 /// assert_eq!(info.to_string(), "Error: No available formula with the name \"ss\".")
 /// ```
-pub fn services_stop(name: &str) -> anyhow::Result<String>{
-    brew(format!("services stop {name}").as_str())
+pub fn services_stop(name: &str) -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "stop", name])
+        .run()
 }
 
 /// 重启服务并注册自启动，就像运行 `brew services restart [name]`
@@ -135,25 +171,31 @@ pub fn services_stop(name: &str) -> anyhow::Result<String>{
 /// // 确保你的机器上已经安装 mpd
 /// homebrew::services_start("mpd").unwrap();
 /// let info = homebrew::services_restart("mpd").unwrap();
-/// assert_eq!(info, r#"Stopping `mpd`... (might take a while)
+/// assert_eq!(info.stdout, r#"Stopping `mpd`... (might take a while)
 /// ==> Successfully stopped `mpd` (label: homebrew.mxcl.mpd)
 /// ==> Successfully started `mpd` (label: homebrew.mxcl.mpd)"#);
 ///
 /// homebrew::services_stop("mpd").unwrap();
 /// let info = homebrew::services_restart("mpd").unwrap();
-/// assert_eq!(info, "==> Successfully started `mpd` (label: homebrew.mxcl.mpd)");
+/// assert_eq!(info.stdout, "==> Successfully started `mpd` (label: homebrew.mxcl.mpd)");
 ///
 /// ```
-pub fn services_restart(name: &str) -> anyhow::Result<String>{
-    brew(format!("services restart {name}").as_str())
+pub fn services_restart(name: &str) -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "restart", name])
+        .run()
 }
 
 /// 终止服务但保持自启动，就像运行 `brew services kill [name]`
-pub fn services_kill(name: &str) -> anyhow::Result<String>{
-    brew(format!("services kill {name}").as_str())
+pub fn services_kill(name: &str) -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "kill", name])
+        .run()
 }
 
 /// 移除所有没用的服务，就像运行 `brew services cleanup`
-pub fn services_cleanup() -> anyhow::Result<String>{
-    brew(format!("services cleanup").as_str())
+pub fn services_cleanup() -> Result<BrewOutput>{
+    Brew::default()
+        .args(["services", "cleanup"])
+        .run()
 }
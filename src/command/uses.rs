@@ -0,0 +1,97 @@
+use crate::error::Result;
+
+use crate::{info_all, info_installed, Brew, Dependent};
+
+/// 查询哪些 `Formula` 依赖 `name`，通过 [`crate::Package::uses`] 在本地计算，包含依赖类型和安装状态
+///
+/// `installed_only` 为 `true` 时只获取已安装的包 `brew info --installed --json=v2`，
+/// 否则需要获取全部的包 `brew info --eval-all --json=v2`，会比较慢
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json").unwrap())));
+///
+/// fn main() {
+///     let deps = brew::uses("openssl@3", true, false).unwrap();
+///     let names: Vec<_> = deps.iter().map(|d| d.name.as_str()).collect();
+///
+///     assert_eq!(names, ["curl", "wget"]);
+///     assert!(deps.iter().all(|d| d.direct && d.installed_on_request));
+/// }
+/// ```
+pub fn uses(name: &str, installed_only: bool, recursive: bool) -> Result<Vec<Dependent>> {
+    let pkg = if installed_only { info_installed()? } else { info_all()? };
+    Ok(pkg.uses(name, installed_only, recursive))
+}
+
+/// 执行 `brew uses --installed [--recursive] {name}` 命令
+///
+/// 结果中的包以 `brew` 的输出为准，再通过 `brew info --installed --json=v2` 补充依赖类型和安装状态，
+/// 本地计算见 [`crate::Package::uses`]，不需要执行 `brew uses` 时使用 [`uses`]
+///
+/// Examples
+///
+/// ```
+/// extern crate homebrew as brew;
+/// # use std::sync::Arc;
+/// # use brew::{MockExecutor, MockResponse};
+/// # brew::set_default_executor(Arc::new(MockExecutor::new()
+/// #     .on(["uses", "--installed", "--recursive", "libunistring"], MockResponse::stdout("curl\nlibidn2\nwget\n"))
+/// #     .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json").unwrap())));
+///
+/// fn main() {
+///     let deps = brew::uses_installed("libunistring", true).unwrap();
+///     let names: Vec<_> = deps.iter().map(|d| d.name.as_str()).collect();
+///
+///     assert_eq!(names, ["curl", "libidn2", "wget"]);
+///     assert!(!deps[0].direct && deps[1].direct);
+/// }
+/// ```
+pub fn uses_installed(name: &str, recursive: bool) -> Result<Vec<Dependent>> {
+    let mut brew = Brew::default();
+    brew.args(["uses", "--installed"]);
+    if recursive {
+        brew.arg("--recursive");
+    }
+    let names = brew.arg(name).output_vec()?;
+    Ok(info_installed()?.annotate_uses(name, recursive, &names))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{with_executor, DependencyKind, MockExecutor, MockResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_uses() -> Result<()> {
+        let mock = MockExecutor::new()
+            .on(["info", "--installed", "--json=v2"], MockResponse::stdout_file("examples/data/installed.json")?)
+            .on(["uses", "--installed", "--recursive", "libunistring"], MockResponse::stdout("curl\nlibidn2\nwget\n"))
+            .on(["uses", "--installed", "tree"], MockResponse::stdout("pcre2\n"));
+        with_executor(Arc::new(mock), || {
+            let deps = uses("libunistring", true, true)?;
+            assert_eq!(deps, uses_installed("libunistring", true)?);
+
+            let libidn2 = &deps[1];
+            assert_eq!(libidn2.kind, DependencyKind::Required);
+            assert!(libidn2.direct && libidn2.installed_as_dependency && !libidn2.installed_on_request);
+
+            assert!(uses("pcre2", true, true)?.is_empty());
+            assert!(uses("tree", true, false)?.is_empty());
+
+            // 本地计算没有找到的包以 `brew` 的输出为准
+            let pcre2 = uses_installed("tree", false)?.remove(0);
+            assert_eq!((pcre2.name.as_str(), pcre2.kind), ("pcre2", DependencyKind::Runtime));
+            assert!(pcre2.direct && pcre2.installed && pcre2.installed_as_dependency);
+            Ok(())
+        })
+    }
+}
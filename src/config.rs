@@ -1,34 +1,181 @@
-use std::process::Command;
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
 
-use anyhow::{Ok, Result};
+use crate::error::{BrewError, Result};
 
+/// 指定 `brew` 可执行文件路径的环境变量
+pub const BREW_BIN_ENV: &str = "HOMEBREW_BREW_BIN";
 
 const BREW_BIN_ARM64: &str = "/opt/homebrew/bin/brew";
 const BREW_BIN_X86_64: &str = "/usr/local/bin/brew";
+const BREW_BIN_LINUX: &str = "/home/linuxbrew/.linuxbrew/bin/brew";
 
-pub fn get_brew_bin() -> Result<String>{
-    let output = Command::new("uname")
-        .arg("-m")
-        .output()?;   // 执行命令并获取输出
-    if let Some(out) = String::from_utf8_lossy(&output.stdout).strip_suffix("\n") {
-        if out == "x86_64" {
-            return Ok(String::from(BREW_BIN_X86_64));
+/// 查找成功后缓存的路径
+static BREW_BIN: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// 查找 `brew` 可执行文件
+///
+/// 依次尝试以下位置，找到第一个可执行文件后缓存结果:
+///
+/// 1. 环境变量 `HOMEBREW_BREW_BIN`，设置了就只使用这个路径，不可执行时返回 [`BrewError::BrewNotFound`]
+/// 2. 环境变量 `HOMEBREW_PREFIX` 下的 `bin/brew`
+/// 3. `PATH` 中的 `brew`
+/// 4. 默认的安装位置 `/opt/homebrew`、`/usr/local`、`/home/linuxbrew/.linuxbrew` 和 `~/.linuxbrew`
+///
+/// 都找不到时返回 [`BrewError::BrewNotFound`] 并列出尝试过的路径，可以通过 `downcast_ref` 取出
+///
+/// 单次调用可以通过 [`crate::Brew::set_bin`] 指定路径
+pub fn get_brew_bin() -> anyhow::Result<String> {
+    let bin = brew_bin()?;
+    Ok(bin.to_string_lossy().into_owned())
+}
+
+/// [`get_brew_bin`] 的实现，返回原始路径和 [`BrewError`]
+pub(crate) fn brew_bin() -> Result<PathBuf> {
+    if let Some(bin) = BREW_BIN.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return Ok(bin.clone());
+    }
+    let bin = discover(|key| env::var_os(key))?;
+    *BREW_BIN.write().unwrap_or_else(|e| e.into_inner()) = Some(bin.clone());
+    Ok(bin)
+}
+
+/// 清除缓存的 `brew` 路径，下次调用 [`get_brew_bin`] 时重新查找
+pub fn clear_brew_bin_cache() {
+    *BREW_BIN.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 设置了 `HOMEBREW_BREW_BIN` 时只检查它，否则依次查找候选路径
+fn discover<F>(var: F) -> Result<PathBuf>
+    where F: Fn(&str) -> Option<OsString>
+{
+    match var(BREW_BIN_ENV).filter(|v| !v.is_empty()) {
+        Some(bin) => find_brew_bin(vec![PathBuf::from(bin)]),
+        None => find_brew_bin(candidates(var)),
+    }
+}
+
+/// 按优先级列出 `HOMEBREW_BREW_BIN` 以外的所有候选路径
+fn candidates<F>(var: F) -> Vec<PathBuf>
+    where F: Fn(&str) -> Option<OsString>
+{
+    let mut paths = Vec::new();
+    if let Some(prefix) = var("HOMEBREW_PREFIX").filter(|v| !v.is_empty()) {
+        paths.push(Path::new(&prefix).join("bin").join("brew"));
+    }
+    if let Some(path) = var("PATH") {
+        paths.extend(env::split_paths(&path).map(|dir| dir.join("brew")));
+    }
+    paths.push(PathBuf::from(BREW_BIN_ARM64));
+    paths.push(PathBuf::from(BREW_BIN_X86_64));
+    paths.push(PathBuf::from(BREW_BIN_LINUX));
+    if let Some(home) = var("HOME").filter(|v| !v.is_empty()) {
+        paths.push(Path::new(&home).join(".linuxbrew").join("bin").join("brew"));
+    }
+
+    let mut tried: Vec<PathBuf> = Vec::new();
+    for p in paths {
+        if !tried.contains(&p) {
+            tried.push(p);
         }
     }
-    Ok(String::from(BREW_BIN_ARM64))
+    tried
+}
+
+/// 返回第一个可执行的候选路径
+fn find_brew_bin(candidates: Vec<PathBuf>) -> Result<PathBuf> {
+    match candidates.iter().find(|p| is_executable(p)) {
+        Some(bin) => Ok(bin.clone()),
+        None => Err(BrewError::BrewNotFound { tried: candidates }),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
 }
 
 
 #[cfg(test)]
 mod tests {
-    use crate::config::BREW_BIN_ARM64;
+    use std::{collections::HashMap, fs};
+
+    use super::*;
 
-    use super::get_brew_bin;
+    /// 在 `dir` 下创建一个可执行的 `bin/brew`
+    fn fake_brew(dir: &Path) -> PathBuf {
+        let bin = dir.join("bin").join("brew");
+        fs::create_dir_all(bin.parent().unwrap()).unwrap();
+        fs::write(&bin, "#!/bin/sh\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        bin
+    }
 
     #[test]
     fn test_get_bin() {
-        if let Ok(cmd) = get_brew_bin() {
-            assert_eq!(cmd, BREW_BIN_ARM64)
-        }
+        let root = env::current_dir().unwrap().join("target/brew-bin-test");
+        let prefix_bin = fake_brew(&root.join("prefix"));
+        let path_bin = fake_brew(&root.join("path"));
+        let vars: HashMap<&str, OsString> = HashMap::from([
+            ("HOMEBREW_PREFIX", root.join("prefix").into_os_string()),
+            ("PATH", root.join("missing").join("bin").into_os_string()),
+            ("HOME", root.join("home").into_os_string()),
+        ]);
+
+        let paths = candidates(|k| vars.get(k).cloned());
+        assert_eq!(paths[0], prefix_bin);
+        assert_eq!(find_brew_bin(paths).unwrap(), prefix_bin);
+
+        let mut vars = vars.clone();
+        vars.insert(BREW_BIN_ENV, path_bin.clone().into_os_string());
+        assert_eq!(discover(|k| vars.get(k).cloned()).unwrap(), path_bin);
+
+        // 指定的路径不存在时不会使用其他的 brew
+        let missing = root.join("missing/bin/brew");
+        vars.insert(BREW_BIN_ENV, missing.clone().into_os_string());
+        let err = discover(|k| vars.get(k).cloned()).unwrap_err();
+        assert!(matches!(err, BrewError::BrewNotFound { ref tried } if *tried == [missing]));
+
+        vars.remove(BREW_BIN_ENV);
+        vars.remove("HOMEBREW_PREFIX");
+        vars.insert("PATH", env::join_paths([root.join("missing/bin"), root.join("path/bin")]).unwrap());
+        assert_eq!(find_brew_bin(candidates(|k| vars.get(k).cloned())).unwrap(), path_bin);
+    }
+
+    #[test]
+    fn test_brew_not_found() {
+        let root = env::current_dir().unwrap().join("target/brew-bin-missing");
+        let vars: HashMap<&str, OsString> = HashMap::from([
+            ("PATH", root.join("bin").into_os_string()),
+            ("HOME", root.clone().into_os_string()),
+        ]);
+        let paths = candidates(|k| vars.get(k).cloned());
+        assert_eq!(paths, [
+            root.join("bin/brew"),
+            PathBuf::from(BREW_BIN_ARM64),
+            PathBuf::from(BREW_BIN_X86_64),
+            PathBuf::from(BREW_BIN_LINUX),
+            root.join(".linuxbrew/bin/brew"),
+        ]);
+        // 测试机器上可能安装了 brew，只检查候选路径为空时的错误
+        let err = find_brew_bin(vec![root.join("bin/brew")]).unwrap_err();
+        assert!(matches!(err, BrewError::BrewNotFound { ref tried } if tried.len() == 1));
     }
 }
@@ -0,0 +1,296 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsString,
+    path::PathBuf,
+    sync::RwLock,
+};
+
+/// 常用的 `HOMEBREW_*` 环境变量
+///
+/// 为 `None` 的字段不会被设置，布尔类型为 `false` 时设置为空字符串，`brew` 会当作没有设置
+///
+/// 可以通过 [`crate::Brew::set_homebrew_env`] 应用到单个命令，或者通过 [`set_default_env`] 应用到之后新建的所有命令
+///
+/// Examples
+///
+/// ```
+/// use homebrew::HomebrewEnv;
+///
+/// let env = HomebrewEnv::new()
+///     .no_analytics(true)
+///     .no_install_cleanup(true)
+///     .bottle_domain("https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles");
+///
+/// let vars = env.vars();
+/// assert_eq!(vars["HOMEBREW_NO_ANALYTICS"], "1");
+/// assert_eq!(vars["HOMEBREW_BOTTLE_DOMAIN"], "https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles");
+/// assert!(!vars.contains_key("HOMEBREW_API_DOMAIN"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HomebrewEnv {
+    /// `HOMEBREW_NO_AUTO_UPDATE`
+    pub no_auto_update: Option<bool>,
+
+    /// `HOMEBREW_NO_INSTALL_CLEANUP`
+    pub no_install_cleanup: Option<bool>,
+
+    /// `HOMEBREW_NO_ANALYTICS`
+    pub no_analytics: Option<bool>,
+
+    /// `HOMEBREW_NO_ENV_HINTS`
+    pub no_env_hints: Option<bool>,
+
+    /// `HOMEBREW_NO_INSTALL_FROM_API`
+    pub no_install_from_api: Option<bool>,
+
+    /// `HOMEBREW_API_DOMAIN`
+    pub api_domain: Option<String>,
+
+    /// `HOMEBREW_BOTTLE_DOMAIN`
+    pub bottle_domain: Option<String>,
+
+    /// `HOMEBREW_BREW_GIT_REMOTE`
+    pub brew_git_remote: Option<String>,
+
+    /// `HOMEBREW_CORE_GIT_REMOTE`
+    pub core_git_remote: Option<String>,
+
+    /// `HOMEBREW_PIP_INDEX_URL`
+    pub pip_index_url: Option<String>,
+
+    /// `HOMEBREW_CACHE`
+    pub cache: Option<PathBuf>,
+
+    /// `HOMEBREW_GITHUB_API_TOKEN`
+    pub github_api_token: Option<String>,
+
+    /// `HOMEBREW_MAKE_JOBS`
+    pub make_jobs: Option<u32>,
+}
+
+const NO_AUTO_UPDATE: &str = "HOMEBREW_NO_AUTO_UPDATE";
+const NO_INSTALL_CLEANUP: &str = "HOMEBREW_NO_INSTALL_CLEANUP";
+const NO_ANALYTICS: &str = "HOMEBREW_NO_ANALYTICS";
+const NO_ENV_HINTS: &str = "HOMEBREW_NO_ENV_HINTS";
+const NO_INSTALL_FROM_API: &str = "HOMEBREW_NO_INSTALL_FROM_API";
+const API_DOMAIN: &str = "HOMEBREW_API_DOMAIN";
+const BOTTLE_DOMAIN: &str = "HOMEBREW_BOTTLE_DOMAIN";
+const BREW_GIT_REMOTE: &str = "HOMEBREW_BREW_GIT_REMOTE";
+const CORE_GIT_REMOTE: &str = "HOMEBREW_CORE_GIT_REMOTE";
+const PIP_INDEX_URL: &str = "HOMEBREW_PIP_INDEX_URL";
+const CACHE: &str = "HOMEBREW_CACHE";
+const GITHUB_API_TOKEN: &str = "HOMEBREW_GITHUB_API_TOKEN";
+const MAKE_JOBS: &str = "HOMEBREW_MAKE_JOBS";
+
+/// 通过 [`set_default_env`] 设置的全局环境变量
+static DEFAULT_ENV: RwLock<Option<HomebrewEnv>> = RwLock::new(None);
+
+impl HomebrewEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从当前进程的环境变量中读取
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| env::var_os(key))
+    }
+
+    /// 通过读取函数新建，读取函数返回 `None` 表示没有设置
+    fn from_vars<F>(var: F) -> Self
+        where F: Fn(&str) -> Option<OsString>
+    {
+        let string = |key: &str| var(key).map(|v| v.to_string_lossy().into_owned());
+        let flag = |key: &str| string(key).map(|v| !v.is_empty());
+        Self {
+            no_auto_update: flag(NO_AUTO_UPDATE),
+            no_install_cleanup: flag(NO_INSTALL_CLEANUP),
+            no_analytics: flag(NO_ANALYTICS),
+            no_env_hints: flag(NO_ENV_HINTS),
+            no_install_from_api: flag(NO_INSTALL_FROM_API),
+            api_domain: string(API_DOMAIN),
+            bottle_domain: string(BOTTLE_DOMAIN),
+            brew_git_remote: string(BREW_GIT_REMOTE),
+            core_git_remote: string(CORE_GIT_REMOTE),
+            pip_index_url: string(PIP_INDEX_URL),
+            cache: var(CACHE).map(PathBuf::from),
+            github_api_token: string(GITHUB_API_TOKEN),
+            make_jobs: string(MAKE_JOBS).and_then(|v| v.trim().parse().ok()),
+        }
+    }
+
+    /// 设置 `HOMEBREW_NO_AUTO_UPDATE`
+    pub fn no_auto_update(mut self, value: bool) -> Self {
+        self.no_auto_update = Some(value);
+        self
+    }
+
+    /// 设置 `HOMEBREW_NO_INSTALL_CLEANUP`
+    pub fn no_install_cleanup(mut self, value: bool) -> Self {
+        self.no_install_cleanup = Some(value);
+        self
+    }
+
+    /// 设置 `HOMEBREW_NO_ANALYTICS`
+    pub fn no_analytics(mut self, value: bool) -> Self {
+        self.no_analytics = Some(value);
+        self
+    }
+
+    /// 设置 `HOMEBREW_NO_ENV_HINTS`
+    pub fn no_env_hints(mut self, value: bool) -> Self {
+        self.no_env_hints = Some(value);
+        self
+    }
+
+    /// 设置 `HOMEBREW_NO_INSTALL_FROM_API`
+    pub fn no_install_from_api(mut self, value: bool) -> Self {
+        self.no_install_from_api = Some(value);
+        self
+    }
+
+    /// 设置 `HOMEBREW_API_DOMAIN`
+    pub fn api_domain<S: Into<String>>(mut self, value: S) -> Self {
+        self.api_domain = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_BOTTLE_DOMAIN`
+    pub fn bottle_domain<S: Into<String>>(mut self, value: S) -> Self {
+        self.bottle_domain = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_BREW_GIT_REMOTE`
+    pub fn brew_git_remote<S: Into<String>>(mut self, value: S) -> Self {
+        self.brew_git_remote = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_CORE_GIT_REMOTE`
+    pub fn core_git_remote<S: Into<String>>(mut self, value: S) -> Self {
+        self.core_git_remote = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_PIP_INDEX_URL`
+    pub fn pip_index_url<S: Into<String>>(mut self, value: S) -> Self {
+        self.pip_index_url = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_CACHE`
+    pub fn cache<P: Into<PathBuf>>(mut self, value: P) -> Self {
+        self.cache = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_GITHUB_API_TOKEN`
+    pub fn github_api_token<S: Into<String>>(mut self, value: S) -> Self {
+        self.github_api_token = Some(value.into());
+        self
+    }
+
+    /// 设置 `HOMEBREW_MAKE_JOBS`
+    pub fn make_jobs(mut self, value: u32) -> Self {
+        self.make_jobs = Some(value);
+        self
+    }
+
+    /// 转换成要设置的环境变量，只包含不为 `None` 的字段
+    pub fn vars(&self) -> BTreeMap<String, String> {
+        let flag = |v: bool| if v { "1".to_string() } else { String::new() };
+        let vars = [
+            (NO_AUTO_UPDATE, self.no_auto_update.map(flag)),
+            (NO_INSTALL_CLEANUP, self.no_install_cleanup.map(flag)),
+            (NO_ANALYTICS, self.no_analytics.map(flag)),
+            (NO_ENV_HINTS, self.no_env_hints.map(flag)),
+            (NO_INSTALL_FROM_API, self.no_install_from_api.map(flag)),
+            (API_DOMAIN, self.api_domain.clone()),
+            (BOTTLE_DOMAIN, self.bottle_domain.clone()),
+            (BREW_GIT_REMOTE, self.brew_git_remote.clone()),
+            (CORE_GIT_REMOTE, self.core_git_remote.clone()),
+            (PIP_INDEX_URL, self.pip_index_url.clone()),
+            (CACHE, self.cache.as_ref().map(|p| p.display().to_string())),
+            (GITHUB_API_TOKEN, self.github_api_token.clone()),
+            (MAKE_JOBS, self.make_jobs.map(|n| n.to_string())),
+        ];
+        vars.into_iter()
+            .filter_map(|(k, v)| v.map(|v| (k.to_string(), v)))
+            .collect()
+    }
+}
+
+/// 设置全局默认的环境变量，之后新建的 [`crate::Brew`] 都会带上
+///
+/// 优先级高于 `Brew::default()` 自带的 `HOMEBREW_NO_AUTO_UPDATE=1`，低于 [`crate::Brew::set_env`]
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{Brew, HomebrewEnv, MockExecutor, MockResponse};
+///
+/// homebrew::set_default_env(HomebrewEnv::new().no_auto_update(false).no_analytics(true));
+///
+/// let inv = Brew::default().arg("update").invocation();
+/// assert_eq!(inv.envs["HOMEBREW_NO_AUTO_UPDATE"], "");
+/// assert_eq!(inv.envs["HOMEBREW_NO_ANALYTICS"], "1");
+///
+/// // `brew`、`brew_run` 和 `brew_spawn` 也一样
+/// let mock = Arc::new(MockExecutor::new().on(["update"], MockResponse::stdout("Already up-to-date.\n")));
+/// homebrew::with_executor(mock.clone(), || homebrew::brew_run("update")).unwrap();
+/// assert_eq!(mock.invocations()[0].envs["HOMEBREW_NO_AUTO_UPDATE"], "");
+/// ```
+pub fn set_default_env(env: HomebrewEnv) {
+    *DEFAULT_ENV.write().unwrap_or_else(|e| e.into_inner()) = Some(env);
+}
+
+/// 获取通过 [`set_default_env`] 设置的环境变量，没有设置时为空
+pub fn default_env() -> HomebrewEnv {
+    DEFAULT_ENV.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::Brew;
+
+    use super::*;
+
+    #[test]
+    fn test_from_vars() {
+        let vars: HashMap<&str, &str> = HashMap::from([
+            ("HOMEBREW_NO_ANALYTICS", "1"),
+            ("HOMEBREW_NO_AUTO_UPDATE", ""),
+            ("HOMEBREW_CACHE", "/tmp/brew-cache"),
+            ("HOMEBREW_MAKE_JOBS", "8"),
+            ("HOMEBREW_API_DOMAIN", "https://mirrors.ustc.edu.cn/homebrew-bottles/api"),
+        ]);
+        let env = HomebrewEnv::from_vars(|k| vars.get(k).map(OsString::from));
+
+        assert_eq!(env, HomebrewEnv::new()
+            .no_analytics(true)
+            .no_auto_update(false)
+            .cache("/tmp/brew-cache")
+            .make_jobs(8)
+            .api_domain("https://mirrors.ustc.edu.cn/homebrew-bottles/api"));
+
+        let roundtrip = env.vars();
+        assert_eq!(HomebrewEnv::from_vars(|k| roundtrip.get(k).map(OsString::from)), env);
+    }
+
+    #[test]
+    fn test_set_homebrew_env() {
+        let env = HomebrewEnv::new().no_install_cleanup(true).github_api_token("ghp_xxx");
+        let inv = Brew::default()
+            .set_homebrew_env(&env)
+            .set_env("HOMEBREW_NO_INSTALL_CLEANUP", "")
+            .invocation();
+        assert_eq!(inv.envs["HOMEBREW_NO_AUTO_UPDATE"], "1");
+        assert_eq!(inv.envs["HOMEBREW_NO_INSTALL_CLEANUP"], "");
+        assert_eq!(inv.envs["HOMEBREW_GITHUB_API_TOKEN"], "ghp_xxx");
+    }
+}
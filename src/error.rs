@@ -0,0 +1,367 @@
+use std::{fmt, io, path::PathBuf, time::Duration};
+
+/// `command::*` 系列函数统一使用的返回类型
+pub type Result<T, E = BrewError> = std::result::Result<T, E>;
+
+/// 执行 `brew` 命令时可能出现的错误
+///
+/// 常见的错误会根据 `brew` 的输出解析成对应的类型，无法识别的统一归为 [`BrewError::NonZeroExit`]
+///
+/// 所有类型的 `to_string()` 都会还原成 `brew` 原本的提示信息
+///
+/// Examples
+///
+/// ```
+/// use homebrew::BrewError;
+///
+/// let err = BrewError::classify(Some(1), "Error: No such keg: /opt/homebrew/Cellar/gotop\n");
+///
+/// assert!(matches!(err, BrewError::NoSuchKeg { ref keg } if keg == "/opt/homebrew/Cellar/gotop"));
+/// assert_eq!(err.to_string(), "Error: No such keg: /opt/homebrew/Cellar/gotop");
+/// ```
+#[derive(Debug)]
+pub enum BrewError {
+    /// `Error: No such keg: [path]`
+    NoSuchKeg {
+        keg: String,
+    },
+
+    /// `Error: No available formula with the name "[name]".`
+    FormulaUnavailable {
+        name: String,
+    },
+
+    /// ``Warning: Service `[name]` is not started.``
+    ServiceNotStarted {
+        name: String,
+    },
+
+    /// `Warning: [name] [version] is already installed and up-to-date.`
+    AlreadyInstalled {
+        name: String,
+        version: Option<String>,
+    },
+
+    /// 其他 `brew` 进程正在执行，比如
+    /// ``Error: A `brew install wget` process has already locked /opt/homebrew/Cellar/wget.``
+    ///
+    /// `message` 为不带 `Error: ` 前缀的原始信息，`path` 为被锁住的路径
+    Locked {
+        path: Option<String>,
+        message: String,
+    },
+
+    /// 下载失败，比如 `curl: (6) Could not resolve host: ghcr.io`
+    ///
+    /// `code` 为 curl 的错误码，`message` 为后面的描述
+    CurlFailed {
+        code: i32,
+        message: String,
+    },
+
+    /// 拉取 git 仓库失败，比如
+    /// `fatal: unable to access 'https://github.com/Homebrew/brew/': Could not resolve host: github.com`
+    GitFetchFailed {
+        url: String,
+        message: String,
+    },
+
+    /// 需要输入 `sudo` 密码但是没有终端，比如
+    /// `sudo: a terminal is required to read the password; either use the -S option to read from standard input or configure an askpass helper`
+    ///
+    /// 可以通过 [`crate::Brew::set_askpass`] 提供密码，`message` 为不带 `sudo: ` 前缀的原始信息
+    SudoRequired {
+        message: String,
+    },
+
+    /// 等待 [`crate::MutationLock`] 超时
+    LockTimeout {
+        path: PathBuf,
+        wait: Duration,
+    },
+
+    /// 找不到 `brew` 可执行文件，`tried` 为尝试过的路径
+    BrewNotFound {
+        tried: Vec<PathBuf>,
+    },
+
+    /// 命令执行失败且无法识别具体原因
+    NonZeroExit {
+        code: Option<i32>,
+        stderr: String,
+    },
+
+    /// 命令超时被终止，保留了终止前已经输出的内容
+    Timeout {
+        timeout: Duration,
+        stdout: String,
+        stderr: String,
+    },
+
+    /// 执行 [`crate::Brew::spawn`] 时输出了等待用户输入的提示，比如 `Password:` 或 `[y/N]`，
+    /// 命令已经被终止，`prompt` 为提示所在的行
+    ///
+    /// 需要回答提示时请用 [`crate::StdinMode::Inherit`]
+    InteractivePromptDetected {
+        prompt: String,
+    },
+
+    /// 命令被 [`crate::CancelHandle`] 取消，保留了终止前已经输出的内容
+    Cancelled {
+        stdout: String,
+        stderr: String,
+    },
+
+    /// 执行 [`crate::autoremove`] 前重新计算的可删除包和确认的计划不一致，没有删除任何东西
+    ///
+    /// `confirmed` 为确认的包名，`current` 为当前可以删除的包名，都按名称排序
+    PlanChanged {
+        confirmed: Vec<String>,
+        current: Vec<String>,
+    },
+
+    /// 解析命令输出失败
+    Parse(String),
+
+    /// 启动或读取进程时的 IO 错误
+    Io(io::Error),
+}
+
+impl BrewError {
+    /// 根据退出码和 `stderr` 内容解析出具体的错误类型
+    ///
+    /// 先在全部输出中查找错误，找不到时才根据 `Warning:` 解析，
+    /// 存在无法识别的 `Error:` 时它才是真正的失败原因，返回 [`BrewError::NonZeroExit`]
+    pub fn classify(code: Option<i32>, stderr: &str) -> Self {
+        let lines = || stderr.lines().map(str::trim);
+        if let Some(err) = lines().find_map(Self::classify_error) {
+            return err;
+        }
+        if !lines().any(|l| l.starts_with("Error: ")) {
+            if let Some(err) = lines().find_map(Self::classify_warning) {
+                return err;
+            }
+        }
+        let stderr = stderr.strip_suffix('\n').unwrap_or(stderr);
+        Self::NonZeroExit { code, stderr: stderr.to_string() }
+    }
+
+    /// 错误类型的名称，比如 `no_such_keg`，用于统计和日志
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BrewError::NoSuchKeg { .. } => "no_such_keg",
+            BrewError::FormulaUnavailable { .. } => "formula_unavailable",
+            BrewError::ServiceNotStarted { .. } => "service_not_started",
+            BrewError::AlreadyInstalled { .. } => "already_installed",
+            BrewError::Locked { .. } => "locked",
+            BrewError::CurlFailed { .. } => "curl_failed",
+            BrewError::GitFetchFailed { .. } => "git_fetch_failed",
+            BrewError::SudoRequired { .. } => "sudo_required",
+            BrewError::LockTimeout { .. } => "lock_timeout",
+            BrewError::BrewNotFound { .. } => "brew_not_found",
+            BrewError::NonZeroExit { .. } => "non_zero_exit",
+            BrewError::Timeout { .. } => "timeout",
+            BrewError::InteractivePromptDetected { .. } => "interactive_prompt",
+            BrewError::Cancelled { .. } => "cancelled",
+            BrewError::PlanChanged { .. } => "plan_changed",
+            BrewError::Parse(_) => "parse",
+            BrewError::Io(_) => "io",
+        }
+    }
+
+    /// 解析单行错误输出，无法识别时返回 `None`
+    fn classify_error(line: &str) -> Option<Self> {
+        if let Some(keg) = line.strip_prefix("Error: No such keg: ") {
+            return Some(Self::NoSuchKeg { keg: keg.to_string() });
+        }
+        if let Some(rest) = line.strip_prefix("Error: No available formula with the name \"") {
+            let name = rest.strip_suffix("\".").unwrap_or(rest);
+            return Some(Self::FormulaUnavailable { name: name.to_string() });
+        }
+        if let Some(message) = line.strip_prefix("Error: ") {
+            if let Some((_, path)) = message.split_once(" process has already locked ")
+                .or_else(|| message.split_once(" process is already using "))
+            {
+                let path = path.strip_suffix('.').unwrap_or(path);
+                return Some(Self::Locked { path: Some(path.to_string()), message: message.to_string() });
+            }
+            if message.starts_with("Another active Homebrew ") {
+                return Some(Self::Locked { path: None, message: message.to_string() });
+            }
+        }
+        if let Some(rest) = line.strip_prefix("curl: (") {
+            if let Some((code, message)) = rest.split_once(") ") {
+                if let Ok(code) = code.parse() {
+                    return Some(Self::CurlFailed { code, message: message.to_string() });
+                }
+            }
+        }
+        if let Some(rest) = line.strip_prefix("fatal: unable to access '") {
+            if let Some((url, message)) = rest.split_once("': ") {
+                return Some(Self::GitFetchFailed { url: url.to_string(), message: message.to_string() });
+            }
+        }
+        if let Some(message) = line.strip_prefix("sudo: ") {
+            if message.contains("password") || message.contains("askpass") {
+                return Some(Self::SudoRequired { message: message.to_string() });
+            }
+        }
+        None
+    }
+
+    /// 解析单行警告输出，只在没有错误时使用
+    fn classify_warning(line: &str) -> Option<Self> {
+        if let Some(rest) = line.strip_prefix("Warning: Service `") {
+            if let Some(name) = rest.strip_suffix("` is not started.") {
+                return Some(Self::ServiceNotStarted { name: name.to_string() });
+            }
+        }
+        if let Some(rest) = line.strip_prefix("Warning: ") {
+            if let Some(pkg) = rest.strip_suffix(" is already installed and up-to-date.") {
+                let (name, version) = match pkg.split_once(' ') {
+                    Some((name, version)) => (name, Some(version.to_string())),
+                    None => (pkg, None),
+                };
+                return Some(Self::AlreadyInstalled { name: name.to_string(), version });
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for BrewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrewError::NoSuchKeg { keg } => write!(f, "Error: No such keg: {keg}"),
+            BrewError::FormulaUnavailable { name } => {
+                write!(f, "Error: No available formula with the name \"{name}\".")
+            }
+            BrewError::ServiceNotStarted { name } => {
+                write!(f, "Warning: Service `{name}` is not started.")
+            }
+            BrewError::AlreadyInstalled { name, version: Some(version) } => {
+                write!(f, "Warning: {name} {version} is already installed and up-to-date.")
+            }
+            BrewError::AlreadyInstalled { name, version: None } => {
+                write!(f, "Warning: {name} is already installed and up-to-date.")
+            }
+            BrewError::Locked { message, .. } => write!(f, "Error: {message}"),
+            BrewError::CurlFailed { code, message } => write!(f, "curl: ({code}) {message}"),
+            BrewError::GitFetchFailed { url, message } => {
+                write!(f, "fatal: unable to access '{url}': {message}")
+            }
+            BrewError::SudoRequired { message } => write!(f, "sudo: {message}"),
+            BrewError::LockTimeout { path, wait } => {
+                write!(f, "timed out after {:.1}s waiting for lock {}", wait.as_secs_f64(), path.display())
+            }
+            BrewError::BrewNotFound { tried } => {
+                let tried: Vec<String> = tried.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "brew executable not found, tried: {}", tried.join(", "))
+            }
+            BrewError::NonZeroExit { code: Some(code), stderr } if stderr.is_empty() => {
+                write!(f, "brew exited with code {code}")
+            }
+            BrewError::NonZeroExit { stderr, .. } => write!(f, "{stderr}"),
+            BrewError::Timeout { timeout, .. } => {
+                write!(f, "brew timed out after {:.1}s", timeout.as_secs_f64())
+            }
+            BrewError::InteractivePromptDetected { prompt } => {
+                write!(f, "brew is waiting for input: {prompt}")
+            }
+            BrewError::Cancelled { .. } => write!(f, "brew was cancelled"),
+            BrewError::PlanChanged { confirmed, current } => write!(
+                f,
+                "autoremove plan changed: confirmed [{}], now [{}]",
+                confirmed.join(", "),
+                current.join(", "),
+            ),
+            BrewError::Parse(e) => write!(f, "{e}"),
+            BrewError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for BrewError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BrewError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BrewError {
+    fn from(e: io::Error) -> Self {
+        BrewError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BrewError {
+    fn from(e: serde_json::Error) -> Self {
+        BrewError::Parse(e.to_string())
+    }
+}
+
+/// 结构体的 `from` 方法返回的都是解析错误
+impl From<anyhow::Error> for BrewError {
+    fn from(e: anyhow::Error) -> Self {
+        BrewError::Parse(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let err = BrewError::classify(Some(1), "Error: No available formula with the name \"ss\".\n");
+        assert!(matches!(err, BrewError::FormulaUnavailable { ref name } if name == "ss"));
+        assert_eq!(err.to_string(), "Error: No available formula with the name \"ss\".");
+
+        let err = BrewError::classify(Some(1), "Warning: Service `mpd` is not started.\n");
+        assert!(matches!(err, BrewError::ServiceNotStarted { ref name } if name == "mpd"));
+        assert_eq!(err.to_string(), "Warning: Service `mpd` is not started.");
+
+        let stderr = "Warning: wget 1.25.0 is already installed and up-to-date.\nTo reinstall 1.25.0, run:\n  brew reinstall wget\n";
+        let err = BrewError::classify(Some(0), stderr);
+        assert!(matches!(
+            err,
+            BrewError::AlreadyInstalled { ref name, version: Some(ref v) } if name == "wget" && v == "1.25.0"
+        ));
+
+        let stderr = "Error: A `brew install wget` process has already locked /opt/homebrew/Cellar/wget.\nPlease wait for it to finish or terminate it to continue.\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::Locked { path: Some(ref p), .. } if p == "/opt/homebrew/Cellar/wget"));
+        assert_eq!(err.to_string(), stderr.lines().next().unwrap());
+
+        let err = BrewError::classify(Some(1), "Error: Another active Homebrew update process is already in progress.\n");
+        assert!(matches!(err, BrewError::Locked { path: None, .. }));
+
+        let stderr = "curl: (6) Could not resolve host: ghcr.io\nError: wget: Failed to download resource \"wget\"\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::CurlFailed { code: 6, .. }));
+        assert_eq!(err.to_string(), "curl: (6) Could not resolve host: ghcr.io");
+
+        let stderr = "fatal: unable to access 'https://github.com/Homebrew/brew/': Could not resolve host: github.com\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::GitFetchFailed { ref url, .. } if url == "https://github.com/Homebrew/brew/"));
+        assert_eq!(err.to_string(), stderr.trim_end());
+
+        let stderr = "==> Running installer for zoom with sudo; the password may be necessary.\nsudo: a terminal is required to read the password; either use the -S option to read from standard input or configure an askpass helper\nsudo: a password is required\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::SudoRequired { ref message } if message.starts_with("a terminal is required")));
+
+        // 警告后面的错误才是真正的失败原因
+        let stderr = "Warning: Service `mpd` is not started.\nError: No such keg: /opt/homebrew/Cellar/mpd\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::NoSuchKeg { ref keg } if keg == "/opt/homebrew/Cellar/mpd"));
+        let stderr = "Warning: wget 1.25.0 is already installed and up-to-date.\nError: Cannot link wget\n";
+        let err = BrewError::classify(Some(1), stderr);
+        assert!(matches!(err, BrewError::NonZeroExit { ref stderr, .. } if stderr.ends_with("Cannot link wget")));
+
+        let err = BrewError::classify(Some(1), "Error: Unknown command: foo\n");
+        assert!(matches!(err, BrewError::NonZeroExit { code: Some(1), .. }));
+        assert_eq!(err.to_string(), "Error: Unknown command: foo");
+    }
+}
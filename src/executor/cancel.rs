@@ -0,0 +1,83 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// 取消正在执行的 `brew` 命令
+///
+/// 可以跨线程克隆和调用，取消后 `brew` 进程及其进程组会被终止，命令返回 [`crate::BrewError::Cancelled`]
+///
+/// Examples
+///
+/// ```ignore
+/// use std::{thread, time::Duration};
+///
+/// use homebrew::{Brew, CancelHandle};
+///
+/// let handle = CancelHandle::new();
+/// let h = handle.clone();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_secs(10));
+///     h.cancel();
+/// });
+///
+/// let err = Brew::new("update").set_cancel_handle(&handle).run().unwrap_err();
+/// assert!(matches!(err, homebrew::BrewError::Cancelled { .. }));
+/// ```
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+    cancelled: Arc<AtomicBool>,
+    /// 通过 [`CancelHandle::child`] 创建时的上级，上级取消时同样视为取消
+    parent: Option<Arc<AtomicBool>>,
+    /// 是否为 [`crate::Brew`] 为检测提示而内部创建，这种句柄不会让 `brew` 运行在独立的进程组中
+    internal: bool,
+}
+
+impl CancelHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 取消命令
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已经取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self.parent.as_ref().is_some_and(|p| p.load(Ordering::SeqCst))
+    }
+
+    /// 新建一个跟随当前句柄的句柄，取消它不会影响当前句柄
+    pub(crate) fn child(&self) -> Self {
+        Self { cancelled: Arc::default(), parent: Some(self.cancelled.clone()), internal: false }
+    }
+
+    /// 新建一个内部使用的句柄，用于用户没有设置句柄时检测到提示后终止命令
+    pub(crate) fn internal() -> Self {
+        Self { internal: true, ..Self::default() }
+    }
+
+    /// 是否为内部使用的句柄
+    pub(crate) fn is_internal(&self) -> bool {
+        self.internal
+    }
+}
+
+impl fmt::Debug for CancelHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancelHandle")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl PartialEq for CancelHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancelled, &other.cancelled)
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex};
+
+use crate::error::Result;
+
+use super::{with_executor, BrewExecutor, ExecOutput, Invocation};
+
+/// 只记录调用、不执行任何命令的执行器
+///
+/// 所有调用都会成功，`stdout` 为渲染后的命令，比如 `HOMEBREW_NO_AUTO_UPDATE=1 brew install wget`，
+/// 不会获取 [`crate::MutationLock`]，也不会通知 [`crate::MetricsObserver`]
+///
+/// 一般通过 [`dry_run`] 使用，异步代码请通过 [`crate::Brew::set_executor`] 设置
+#[derive(Debug, Default)]
+pub struct DryRunExecutor {
+    planned: Mutex<Vec<Invocation>>,
+}
+
+impl DryRunExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 计划执行的全部调用
+    pub fn planned(&self) -> Vec<Invocation> {
+        self.planned.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl BrewExecutor for DryRunExecutor {
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput> {
+        self.planned.lock().unwrap_or_else(|e| e.into_inner()).push(inv.clone());
+        Ok(ExecOutput {
+            code: Some(0),
+            stdout: format!("{inv}\n").into_bytes(),
+            stderr: Vec::new(),
+        })
+    }
+
+    fn is_dry_run(&self) -> bool {
+        true
+    }
+}
+
+/// 在当前线程内以 dry run 模式执行 `f`，返回 `f` 的结果和计划执行的调用
+///
+/// 适用于 `install`、`uninstall`、`upgrade` 和 `services_*` 等会修改系统的命令，
+/// 需要解析输出的命令（比如 [`crate::info`]）在 dry run 模式下会解析失败
+///
+/// 和 [`with_executor`] 一样只对当前线程生效，在其他线程执行的命令（比如被 tokio 调度到其他线程的异步调用）
+/// 会真正执行，这时请给 [`crate::Brew`] 单独设置 [`DryRunExecutor`]
+///
+/// Examples
+///
+/// ```
+/// let (out, planned) = homebrew::dry_run(|| {
+///     homebrew::install("wget")?;
+///     homebrew::services_restart("unbound")
+/// });
+///
+/// assert_eq!(out.unwrap().stdout, "HOMEBREW_NO_AUTO_UPDATE=1 brew services restart unbound");
+/// assert_eq!(planned.len(), 2);
+/// assert_eq!(planned[0].args_lossy(), ["install", "wget"]);
+/// ```
+pub fn dry_run<F, R>(f: F) -> (R, Vec<Invocation>)
+    where F: FnOnce() -> R
+{
+    let executor = Arc::new(DryRunExecutor::new());
+    let res = with_executor(executor.clone(), f);
+    (res, executor.planned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Brew, InMemoryMetrics, MutationLock};
+
+    use super::*;
+
+    #[test]
+    fn test_dry_run_skips_lock_and_metrics() {
+        let lock_dir = std::path::Path::new("target/dry-run-lock-test");
+        let _ = std::fs::remove_dir_all(lock_dir);
+        let metrics = Arc::new(InMemoryMetrics::new());
+        let (out, planned) = dry_run(|| {
+            Brew::new("install wget")
+                .set_lock(MutationLock::new(lock_dir.join("brew.lock")))
+                .set_metrics_observer(metrics.clone())
+                .run()
+        });
+        assert_eq!(out.unwrap().stdout, "brew install wget");
+        assert_eq!(planned.len(), 1);
+        assert!(metrics.records().is_empty());
+        assert!(!lock_dir.exists());
+    }
+}
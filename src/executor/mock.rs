@@ -0,0 +1,123 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Mutex};
+
+use crate::error::Result;
+
+use super::{BrewExecutor, ExecOutput, Invocation};
+
+/// [`MockExecutor`] 中预设的返回结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl MockResponse {
+    /// 执行成功并输出 `stdout`
+    pub fn stdout<S: Into<String>>(stdout: S) -> Self {
+        Self { code: Some(0), stdout: stdout.into(), stderr: String::new() }
+    }
+
+    /// 执行成功并以文件内容作为 `stdout`，比如 `examples/data/wget.json`
+    pub fn stdout_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::stdout(fs::read_to_string(path)?))
+    }
+
+    /// 执行失败，退出码为 `code` 并输出 `stderr`
+    pub fn failure<S: Into<String>>(code: i32, stderr: S) -> Self {
+        Self { code: Some(code), stdout: String::new(), stderr: stderr.into() }
+    }
+
+    /// 追加 `stderr` 输出，比如成功时的警告，可以多次调用
+    pub fn with_stderr<S: AsRef<str>>(mut self, stderr: S) -> Self {
+        self.stderr.push_str(stderr.as_ref());
+        self
+    }
+}
+
+/// 根据参数返回预设结果的执行器，不会启动任何进程
+///
+/// 参数需要完全一致才能匹配，没有匹配的调用会以退出码 1 失败
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{BrewError, MockExecutor, MockResponse};
+///
+/// let mock = Arc::new(MockExecutor::new()
+///     .on(["info", "wget", "--json=v2"], MockResponse::stdout_file("examples/data/wget.json").unwrap())
+///     .on(["uninstall", "gotop"], MockResponse::failure(1, "Error: No such keg: /opt/homebrew/Cellar/gotop\n")));
+///
+/// homebrew::with_executor(mock, || {
+///     let pkg = homebrew::info("wget").unwrap();
+///     assert_eq!(pkg.name, "wget");
+///
+///     let err = homebrew::uninstall("gotop").unwrap_err();
+///     assert!(matches!(err, BrewError::NoSuchKeg { .. }));
+/// });
+/// ```
+#[derive(Debug, Default)]
+pub struct MockExecutor {
+    responses: HashMap<Vec<String>, MockResponse>,
+    invocations: Mutex<Vec<Invocation>>,
+}
+
+impl MockExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为参数 `args` 预设返回结果
+    pub fn on<I, S>(mut self, args: I, response: MockResponse) -> Self
+        where I: IntoIterator<Item = S>, S: Into<String>
+    {
+        let args = args.into_iter().map(Into::into).collect();
+        self.responses.insert(args, response);
+        self
+    }
+
+    /// 已经执行过的全部调用
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 已经执行过的全部调用的参数
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.invocations().iter().map(Invocation::args_lossy).collect()
+    }
+}
+
+impl BrewExecutor for MockExecutor {
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput> {
+        self.invocations.lock().unwrap_or_else(|e| e.into_inner()).push(inv.clone());
+        let args = inv.args_lossy();
+        let out = match self.responses.get(&args) {
+            Some(resp) => ExecOutput {
+                code: resp.code,
+                stdout: resp.stdout.clone().into_bytes(),
+                stderr: resp.stderr.clone().into_bytes(),
+            },
+            None => ExecOutput {
+                code: Some(1),
+                stdout: Vec::new(),
+                stderr: format!("Error: No mock response for `brew {}`\n", args.join(" ")).into_bytes(),
+            },
+        };
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_stderr() {
+        let resp = MockResponse::failure(1, "Error: first\n")
+            .with_stderr("Warning: second\n")
+            .with_stderr("Warning: third\n");
+        assert_eq!(resp.stderr, "Error: first\nWarning: second\nWarning: third\n");
+    }
+}
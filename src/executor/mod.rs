@@ -0,0 +1,347 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    env,
+    ffi::OsString,
+    fmt,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use crate::config::brew_bin;
+use crate::error::Result;
+
+pub mod cancel;
+pub mod dry_run;
+pub mod mock;
+pub mod process;
+mod prompt;
+pub(crate) mod shell;
+pub mod transcript;
+
+pub use cancel::CancelHandle;
+pub use dry_run::{dry_run, DryRunExecutor};
+pub use mock::{MockExecutor, MockResponse};
+pub use process::ProcessExecutor;
+pub(crate) use prompt::detect_prompt;
+pub use transcript::{RecordingExecutor, ReplayExecutor, TranscriptEntry};
+
+/// 一次 `brew` 调用的完整描述，交给 [`BrewExecutor`] 执行
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Invocation {
+    /// `brew` 可执行文件路径，为 `None` 时由执行器自行查找
+    pub program: Option<PathBuf>,
+
+    /// 传给 `brew` 的参数
+    pub args: Vec<OsString>,
+
+    /// 额外设置的环境变量
+    pub envs: HashMap<String, String>,
+
+    /// 超时时间，超时后终止进程并返回 [`crate::BrewError::Timeout`]
+    pub timeout: Option<Duration>,
+
+    /// 取消句柄，取消后终止进程并返回 [`crate::BrewError::Cancelled`]
+    pub cancel: Option<CancelHandle>,
+
+    /// 是否清空继承的环境变量，清空后只保留 `env_allow` 中的变量
+    pub env_clear: bool,
+
+    /// 清空环境变量时保留的变量
+    pub env_allow: Vec<String>,
+
+    /// 不继承的环境变量，`envs` 中设置的变量不受影响
+    pub env_remove: Vec<String>,
+
+    /// 工作目录，为 `None` 时使用当前目录
+    pub current_dir: Option<PathBuf>,
+
+    /// 标准输入，默认为 [`StdinMode::Null`]
+    pub stdin: StdinMode,
+}
+
+/// `brew` 进程的标准输入
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StdinMode {
+    /// 连接到 `/dev/null`，读取时立即得到 EOF，不会卡在等待输入上
+    #[default]
+    Null,
+
+    /// 继承当前进程的标准输入，需要在终端中回答提示时使用
+    Inherit,
+}
+
+impl Invocation {
+    /// 参数转为字符串，非 UTF-8 字符会被替换
+    pub fn args_lossy(&self) -> Vec<String> {
+        self.args.iter().map(|a| a.to_string_lossy().into_owned()).collect()
+    }
+
+    /// 构造要执行的 [`Command`]，没有指定 `program` 时通过 [`crate::get_brew_bin`] 查找
+    pub fn to_command(&self) -> Result<Command> {
+        let bin = match &self.program {
+            Some(p) => p.clone(),
+            None => brew_bin()?,
+        };
+        let mut cmd = Command::new(bin);
+        cmd.args(&self.args);
+        if self.env_clear || !self.env_remove.is_empty() {
+            cmd.env_clear().envs(self.inherited_env());
+        }
+        cmd.envs(&self.envs);
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        cmd.stdin(match self.stdin {
+            StdinMode::Null => Stdio::null(),
+            StdinMode::Inherit => Stdio::inherit(),
+        });
+        Ok(cmd)
+    }
+
+    /// 从当前进程继承的环境变量
+    pub fn inherited_env(&self) -> Vec<(OsString, OsString)> {
+        let vars: Vec<(OsString, OsString)> = if self.env_clear {
+            self.env_allow.iter()
+                .filter_map(|k| env::var_os(k).map(|v| (OsString::from(k), v)))
+                .collect()
+        } else {
+            env::vars_os().collect()
+        };
+        vars.into_iter()
+            .filter(|(k, _)| !self.env_remove.iter().any(|r| k == r.as_str()))
+            .collect()
+    }
+
+    /// 执行时实际生效的环境变量，用于调试，非 UTF-8 字符会被替换
+    pub fn effective_env(&self) -> BTreeMap<String, String> {
+        let mut vars: BTreeMap<String, String> = self.inherited_env().into_iter()
+            .map(|(k, v)| (k.to_string_lossy().into_owned(), v.to_string_lossy().into_owned()))
+            .collect();
+        vars.extend(self.envs.clone());
+        vars
+    }
+
+    /// 额外设置的环境变量，`HOMEBREW_GITHUB_API_TOKEN`、`SUDO_ASKPASS` 等密钥的值会被替换为 `***`，用于日志和录制
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// let inv = homebrew::Brew::new("update")
+    ///     .set_env("HOMEBREW_GITHUB_API_TOKEN", "ghp_xxx")
+    ///     .set_env("HOMEBREW_NO_ANALYTICS", "1")
+    ///     .invocation();
+    /// let envs = inv.redacted_envs();
+    ///
+    /// assert_eq!(envs["HOMEBREW_GITHUB_API_TOKEN"], "***");
+    /// assert_eq!(envs["HOMEBREW_NO_ANALYTICS"], "1");
+    /// ```
+    pub fn redacted_envs(&self) -> BTreeMap<String, String> {
+        self.envs.iter()
+            .map(|(k, v)| {
+                let upper = k.to_ascii_uppercase();
+                let secret = SECRET_ENV_MARKERS.iter().any(|m| upper.contains(m));
+                (k.clone(), if secret { "***".to_string() } else { v.clone() })
+            })
+            .collect()
+    }
+}
+
+/// 参数中的 `brew` 命令，跳过 `--verbose` 等选项，`services` 同时返回子命令，只有选项时返回 `None`
+///
+/// [`crate::MetricsObserver`] 的命令类型和是否需要文件锁都以它为准
+pub(crate) fn subcommand(args: &[OsString]) -> Option<(String, Option<String>)> {
+    let mut words = args.iter()
+        .map(|a| a.to_string_lossy())
+        .filter(|a| !a.starts_with('-'));
+    let cmd = words.next()?.into_owned();
+    let sub = if cmd == "services" { words.next().map(|s| s.into_owned()) } else { None };
+    Some((cmd, sub))
+}
+
+/// 变量名中包含这些内容时视为密钥
+const SECRET_ENV_MARKERS: &[&str] = &["TOKEN", "PASSWORD", "SECRET", "CREDENTIAL", "AUTH", "_KEY", "ASKPASS"];
+
+/// 异步执行器方法返回的 `Future`
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/// 执行器返回的原始输出
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExecOutput {
+    /// 退出码，被信号终止时为 `None`
+    pub code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// 实时输出的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// 执行过程中输出的一行内容，不包含换行符
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrewEvent {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+impl BrewEvent {
+    pub fn stdout<S: Into<String>>(line: S) -> Self {
+        Self { stream: OutputStream::Stdout, line: line.into() }
+    }
+
+    pub fn stderr<S: Into<String>>(line: S) -> Self {
+        Self { stream: OutputStream::Stderr, line: line.into() }
+    }
+}
+
+/// `brew` 命令执行器
+///
+/// [`crate::Brew`] 只负责构造 [`Invocation`]，真正的执行交给执行器，
+/// 默认为启动真实进程的 [`ProcessExecutor`]，测试时可以换成 [`MockExecutor`]
+pub trait BrewExecutor: Send + Sync + fmt::Debug {
+    /// 执行命令并等待结束，收集全部输出
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput>;
+
+    /// 执行命令并逐行回调标准输出和标准错误，返回退出码
+    ///
+    /// 默认实现为执行结束后先回调标准输出，再回调标准错误
+    fn spawn(&self, inv: &Invocation, on_event: &mut dyn FnMut(&BrewEvent)) -> Result<Option<i32>> {
+        let out = self.output(inv)?;
+        for line in String::from_utf8_lossy(&out.stdout).lines() {
+            on_event(&BrewEvent::stdout(line));
+        }
+        for line in String::from_utf8_lossy(&out.stderr).lines() {
+            on_event(&BrewEvent::stderr(line));
+        }
+        Ok(out.code)
+    }
+
+    /// 是否只记录调用而不真正执行，为 `true` 时 [`crate::Brew`] 不会获取文件锁，也不会通知 [`crate::MetricsObserver`]
+    fn is_dry_run(&self) -> bool {
+        false
+    }
+
+    /// [`BrewExecutor::output`] 的异步版本
+    ///
+    /// 默认实现直接调用同步方法，适用于不会阻塞的执行器，比如 [`MockExecutor`]
+    #[cfg(feature = "async")]
+    fn output_async<'a>(&'a self, inv: &'a Invocation) -> BoxFuture<'a, Result<ExecOutput>> {
+        Box::pin(async move { self.output(inv) })
+    }
+
+    /// [`BrewExecutor::spawn`] 的异步版本
+    ///
+    /// 默认实现直接调用同步方法，适用于不会阻塞的执行器，比如 [`MockExecutor`]
+    #[cfg(feature = "async")]
+    fn spawn_async<'a>(
+        &'a self,
+        inv: &'a Invocation,
+        on_event: &'a mut (dyn FnMut(&BrewEvent) + Send),
+    ) -> BoxFuture<'a, Result<Option<i32>>> {
+        Box::pin(async move { self.spawn(inv, on_event) })
+    }
+}
+
+static DEFAULT_EXECUTOR: RwLock<Option<Arc<dyn BrewExecutor>>> = RwLock::new(None);
+
+thread_local! {
+    static SCOPED_EXECUTOR: RefCell<Option<Arc<dyn BrewExecutor>>> = const { RefCell::new(None) };
+}
+
+/// 设置全局默认的执行器，所有没有单独设置执行器的 [`crate::Brew`] 都会使用它
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{MockExecutor, MockResponse};
+///
+/// homebrew::set_default_executor(Arc::new(
+///     MockExecutor::new().on(["--prefix"], MockResponse::stdout("/opt/homebrew\n")),
+/// ));
+///
+/// assert_eq!(homebrew::prefix().unwrap(), "/opt/homebrew");
+/// ```
+pub fn set_default_executor(executor: Arc<dyn BrewExecutor>) {
+    let mut default = DEFAULT_EXECUTOR.write().unwrap_or_else(|e| e.into_inner());
+    *default = Some(executor);
+}
+
+/// 在当前线程内临时替换执行器，`f` 执行结束后恢复
+///
+/// 优先级高于 [`set_default_executor`]，适合并行执行的单元测试
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{MockExecutor, MockResponse};
+///
+/// let mock = Arc::new(MockExecutor::new()
+///     .on(["search", "wget"], MockResponse::stdout("wget\nwget2\nwgetpaste\n")));
+///
+/// let out = homebrew::with_executor(mock.clone(), || homebrew::search("wget")).unwrap();
+///
+/// assert_eq!(out, ["wget", "wget2", "wgetpaste"]);
+/// assert_eq!(mock.calls(), [["search", "wget"]]);
+/// ```
+pub fn with_executor<F, R>(executor: Arc<dyn BrewExecutor>, f: F) -> R
+    where F: FnOnce() -> R
+{
+    /// 保证 `f` panic 时也能恢复之前的执行器
+    struct Restore(Option<Arc<dyn BrewExecutor>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let prev = self.0.take();
+            SCOPED_EXECUTOR.with(|e| *e.borrow_mut() = prev);
+        }
+    }
+
+    let prev = SCOPED_EXECUTOR.with(|e| e.borrow_mut().replace(executor));
+    let _restore = Restore(prev);
+    f()
+}
+
+/// 获取当前生效的执行器
+pub fn current_executor() -> Arc<dyn BrewExecutor> {
+    if let Some(e) = SCOPED_EXECUTOR.with(|e| e.borrow().clone()) {
+        return e;
+    }
+    let default = DEFAULT_EXECUTOR.read().unwrap_or_else(|e| e.into_inner());
+    match default.as_ref() {
+        Some(e) => e.clone(),
+        None => Arc::new(ProcessExecutor),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Brew;
+
+    use super::*;
+
+    #[test]
+    fn test_executor_priority() {
+        let scoped = Arc::new(MockExecutor::new().on(["--prefix"], MockResponse::stdout("/scoped\n")));
+        let own = Arc::new(MockExecutor::new().on(["--prefix"], MockResponse::stdout("/own\n")));
+
+        with_executor(scoped.clone(), || {
+            assert_eq!(Brew::new("--prefix").output().unwrap(), "/scoped");
+            assert_eq!(Brew::new("--prefix").set_executor(own.clone()).output().unwrap(), "/own");
+        });
+
+        assert_eq!(scoped.calls().len(), 1);
+        assert_eq!(own.calls().len(), 1);
+        assert!(SCOPED_EXECUTOR.with(|e| e.borrow().is_none()));
+    }
+}
@@ -0,0 +1,723 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Child, ChildStderr, ChildStdout, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::error::{BrewError, Result};
+
+use super::{detect_prompt, BrewEvent, BrewExecutor, ExecOutput, Invocation, OutputStream};
+
+/// 检查超时和取消的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 发送 `SIGTERM` 后等待进程退出的时间，超过后发送 `SIGKILL`
+const TERM_GRACE: Duration = Duration::from_secs(2);
+
+/// 通过 [`std::process::Command`] 启动真实 `brew` 进程的执行器
+///
+/// 设置了超时或取消句柄时，`brew` 会运行在独立的进程组中，终止时整个进程组都会被结束；
+/// 只开启了提示检测时 `brew` 仍然和调用方在同一个进程组中，检测到提示后只终止 `brew` 进程本身
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProcessExecutor;
+
+impl ProcessExecutor {
+    /// 构造要执行的进程
+    fn command(inv: &Invocation) -> Result<(PathBuf, Command)> {
+        let mut cmd = inv.to_command()?;
+        let bin = PathBuf::from(cmd.get_program());
+        #[cfg(unix)]
+        if own_group(inv) {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0);
+        }
+        Ok((bin, cmd))
+    }
+}
+
+impl BrewExecutor for ProcessExecutor {
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput> {
+        let (bin, mut cmd) = Self::command(inv)?;
+        if is_supervised(inv) {
+            let child = cmd
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| not_found_or_io(e, &bin))?;
+            return supervise(child, inv, None);
+        }
+        let output = cmd.output().map_err(|e| not_found_or_io(e, &bin))?;
+        Ok(ExecOutput {
+            code: output.status.code(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+
+    fn spawn(&self, inv: &Invocation, on_event: &mut dyn FnMut(&BrewEvent)) -> Result<Option<i32>> {
+        let (bin, mut cmd) = Self::command(inv)?;
+        // 标准输出和标准错误都设置为管道，在后台线程中逐行读取
+        let child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| not_found_or_io(e, &bin))?;
+        Ok(supervise(child, inv, Some(on_event))?.code)
+    }
+
+    #[cfg(feature = "async")]
+    fn output_async<'a>(&'a self, inv: &'a Invocation) -> super::BoxFuture<'a, Result<ExecOutput>> {
+        Box::pin(Self::tokio_output(inv))
+    }
+
+    #[cfg(feature = "async")]
+    fn spawn_async<'a>(
+        &'a self,
+        inv: &'a Invocation,
+        on_event: &'a mut (dyn FnMut(&BrewEvent) + Send),
+    ) -> super::BoxFuture<'a, Result<Option<i32>>> {
+        Box::pin(Self::tokio_spawn(inv, on_event))
+    }
+}
+
+/// 是否需要监控超时和取消
+fn is_supervised(inv: &Invocation) -> bool {
+    inv.timeout.is_some() || inv.cancel.is_some()
+}
+
+/// 是否运行在独立的进程组中，只有调用方设置了超时或取消句柄时才需要
+///
+/// 提示检测使用的内部句柄不算，这样在终端中按 Ctrl-C 时 `brew` 仍然会收到信号
+fn own_group(inv: &Invocation) -> bool {
+    inv.timeout.is_some() || inv.cancel.as_ref().is_some_and(|c| !c.is_internal())
+}
+
+/// 管道中读到的数据
+type Chunk = (OutputStream, Vec<u8>);
+
+/// 在后台线程中读取管道
+fn read_pipe<R: Read + Send + 'static>(mut pipe: R, stream: OutputStream, tx: Sender<Chunk>) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.send((stream, buf[..n].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 收集输出，并把完整的行交给回调
+struct Collector<'a, F: FnMut(&BrewEvent) + ?Sized> {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    /// 还没有遇到换行符的内容，分别对应标准输出和标准错误
+    pending: [Vec<u8>; 2],
+    on_event: Option<&'a mut F>,
+}
+
+impl<'a, F: FnMut(&BrewEvent) + ?Sized> Collector<'a, F> {
+    fn new(on_event: Option<&'a mut F>) -> Self {
+        Self { stdout: Vec::new(), stderr: Vec::new(), pending: [Vec::new(), Vec::new()], on_event }
+    }
+
+    fn push(&mut self, (stream, chunk): Chunk) {
+        match stream {
+            OutputStream::Stdout => self.stdout.extend_from_slice(&chunk),
+            OutputStream::Stderr => self.stderr.extend_from_slice(&chunk),
+        }
+        if self.on_event.is_none() {
+            return;
+        }
+        let pending = &mut self.pending[stream as usize];
+        pending.extend_from_slice(&chunk);
+        let mut lines = Vec::new();
+        while let Some(i) = pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=i).collect();
+            lines.push(line);
+        }
+        for line in lines {
+            self.emit(stream, &line[..line.len() - 1]);
+        }
+    }
+
+    /// 输出最后一行没有换行符的内容
+    fn flush(&mut self) {
+        for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+            let line = std::mem::take(&mut self.pending[stream as usize]);
+            if !line.is_empty() {
+                self.emit(stream, &line);
+            }
+        }
+    }
+
+    /// 输出停顿时检查还没有换行符的内容，等待输入的提示通常不以换行结尾，像是提示时提前作为一行回调
+    fn flush_prompt(&mut self) {
+        for stream in [OutputStream::Stdout, OutputStream::Stderr] {
+            let pending = &self.pending[stream as usize];
+            if !pending.is_empty() && detect_prompt(&String::from_utf8_lossy(pending)).is_some() {
+                let line = std::mem::take(&mut self.pending[stream as usize]);
+                self.emit(stream, &line);
+            }
+        }
+    }
+
+    fn emit(&mut self, stream: OutputStream, line: &[u8]) {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if let Some(on_event) = self.on_event.as_mut() {
+            on_event(&BrewEvent { stream, line: String::from_utf8_lossy(line).into_owned() });
+        }
+    }
+
+    /// 读取剩余的输出，进程组被终止后管道很快就会关闭
+    fn drain(&mut self, rx: &Receiver<Chunk>) {
+        let deadline = Instant::now() + TERM_GRACE;
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(left) {
+                Ok(chunk) => self.push(chunk),
+                Err(_) => break,
+            }
+        }
+        self.flush();
+    }
+
+    fn lossy(&self) -> (String, String) {
+        (
+            String::from_utf8_lossy(&self.stdout).into_owned(),
+            String::from_utf8_lossy(&self.stderr).into_owned(),
+        )
+    }
+}
+
+/// 等待进程结束，期间检查超时和取消，触发时终止进程
+///
+/// 没有设置超时和取消句柄时只是等待进程结束，每次轮询没有新输出时检查没有换行符的提示
+fn supervise(mut child: Child, inv: &Invocation, on_event: Option<&mut dyn FnMut(&BrewEvent)>) -> Result<ExecOutput> {
+    let start = Instant::now();
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        read_pipe::<ChildStdout>(stdout, OutputStream::Stdout, tx.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        read_pipe::<ChildStderr>(stderr, OutputStream::Stderr, tx.clone());
+    }
+    drop(tx);
+
+    let mut collector = Collector::new(on_event);
+    let mut pipes_open = true;
+    loop {
+        let timed_out = inv.timeout.filter(|t| start.elapsed() >= *t);
+        let cancelled = inv.cancel.as_ref().is_some_and(|c| c.is_cancelled());
+        if timed_out.is_some() || cancelled {
+            terminate(&mut child, own_group(inv));
+            collector.drain(&rx);
+            let (stdout, stderr) = collector.lossy();
+            return Err(interrupted_error(timed_out, stdout, stderr));
+        }
+
+        if pipes_open {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(chunk) => collector.push(chunk),
+                Err(RecvTimeoutError::Timeout) => collector.flush_prompt(),
+                Err(RecvTimeoutError::Disconnected) => pipes_open = false,
+            }
+        } else if let Some(status) = child.try_wait()? {
+            collector.flush();
+            return Ok(ExecOutput {
+                code: status.code(),
+                stdout: collector.stdout,
+                stderr: collector.stderr,
+            });
+        } else {
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// 超时返回 [`BrewError::Timeout`]，否则为 [`BrewError::Cancelled`]
+fn interrupted_error(timed_out: Option<Duration>, stdout: String, stderr: String) -> BrewError {
+    match timed_out {
+        Some(timeout) => BrewError::Timeout { timeout, stdout, stderr },
+        None => BrewError::Cancelled { stdout, stderr },
+    }
+}
+
+/// 终止进程，先发送 `SIGTERM`，等待一段时间后仍未退出则发送 `SIGKILL`
+///
+/// `group` 为 `true` 时终止整个进程组，否则只终止 `brew` 进程，避免信号发给调用方所在的进程组
+#[cfg(unix)]
+fn terminate(child: &mut Child, group: bool) {
+    let pid = child.id() as libc::pid_t;
+    // SAFETY: 只是向进程或进程组发送信号
+    unsafe { libc::kill(if group { -pid } else { pid }, libc::SIGTERM) };
+    let deadline = Instant::now() + TERM_GRACE;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    if group {
+        // 进程组中可能还有其他进程，统一发送 SIGKILL
+        // SAFETY: 同上
+        unsafe { libc::kill(-pid, libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(not(unix))]
+fn terminate(child: &mut Child, _group: bool) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(feature = "async")]
+impl ProcessExecutor {
+    /// 构造异步进程，`Future` 被丢弃时会杀掉子进程
+    fn tokio_command(inv: &Invocation) -> Result<(PathBuf, tokio::process::Command)> {
+        let (bin, cmd) = Self::command(inv)?;
+        let mut cmd = tokio::process::Command::from(cmd);
+        cmd.kill_on_drop(true);
+        Ok((bin, cmd))
+    }
+
+    async fn tokio_output(inv: &Invocation) -> Result<ExecOutput> {
+        use tokio::io::AsyncReadExt;
+
+        let (bin, mut cmd) = Self::tokio_command(inv)?;
+        if !is_supervised(inv) {
+            let output = cmd.output().await.map_err(|e| not_found_or_io(e, &bin))?;
+            return Ok(ExecOutput {
+                code: output.status.code(),
+                stdout: output.stdout,
+                stderr: output.stderr,
+            });
+        }
+
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| not_found_or_io(e, &bin))?;
+        let (mut out_pipe, mut err_pipe) = match (child.stdout.take(), child.stderr.take()) {
+            (Some(out), Some(err)) => (out, err),
+            _ => return Err(BrewError::Io(io::Error::other("Could not capture output"))),
+        };
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let res = {
+            let work = async {
+                tokio::try_join!(out_pipe.read_to_end(&mut stdout), err_pipe.read_to_end(&mut stderr))?;
+                child.wait().await
+            };
+            tokio::select! {
+                status = work => Ok(status?),
+                timed_out = interrupted(inv) => Err(timed_out),
+            }
+        };
+        match res {
+            Ok(status) => Ok(ExecOutput { code: status.code(), stdout, stderr }),
+            Err(timed_out) => {
+                terminate_async(&mut child, own_group(inv)).await;
+                Err(interrupted_error(
+                    timed_out,
+                    String::from_utf8_lossy(&stdout).into_owned(),
+                    String::from_utf8_lossy(&stderr).into_owned(),
+                ))
+            }
+        }
+    }
+
+    async fn tokio_spawn(inv: &Invocation, on_event: &mut (dyn FnMut(&BrewEvent) + Send)) -> Result<Option<i32>> {
+        use tokio::io::AsyncReadExt;
+
+        let (bin, mut cmd) = Self::tokio_command(inv)?;
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| not_found_or_io(e, &bin))?;
+        let (mut out_pipe, mut err_pipe) = match (child.stdout.take(), child.stderr.take()) {
+            (Some(out), Some(err)) => (out, err),
+            _ => return Err(BrewError::Io(io::Error::other("Could not capture output"))),
+        };
+
+        // 和同步版本一样按块读取，被终止时已经输出的内容放到错误中
+        let mut collector = Collector::new(Some(on_event));
+        let res = {
+            let work = async {
+                let (mut out_buf, mut err_buf) = ([0u8; 8192], [0u8; 8192]);
+                let (mut out_open, mut err_open) = (true, true);
+                while out_open || err_open {
+                    tokio::select! {
+                        n = out_pipe.read(&mut out_buf), if out_open => match n? {
+                            0 => out_open = false,
+                            n => collector.push((OutputStream::Stdout, out_buf[..n].to_vec())),
+                        },
+                        n = err_pipe.read(&mut err_buf), if err_open => match n? {
+                            0 => err_open = false,
+                            n => collector.push((OutputStream::Stderr, err_buf[..n].to_vec())),
+                        },
+                        _ = tokio::time::sleep(POLL_INTERVAL) => collector.flush_prompt(),
+                    }
+                }
+                collector.flush();
+                child.wait().await
+            };
+            tokio::select! {
+                status = work => Ok(status?),
+                timed_out = interrupted(inv) => Err(timed_out),
+            }
+        };
+        match res {
+            Ok(status) => Ok(status.code()),
+            Err(timed_out) => {
+                terminate_async(&mut child, own_group(inv)).await;
+                collector.flush();
+                let (stdout, stderr) = collector.lossy();
+                Err(interrupted_error(timed_out, stdout, stderr))
+            }
+        }
+    }
+}
+
+/// 等到超时或者被取消，超时返回 `Some(timeout)`，取消返回 `None`，两者都没有设置时永远不会完成
+#[cfg(feature = "async")]
+async fn interrupted(inv: &Invocation) -> Option<Duration> {
+    if !is_supervised(inv) {
+        return std::future::pending().await;
+    }
+    let start = Instant::now();
+    loop {
+        if let Some(timeout) = inv.timeout.filter(|t| start.elapsed() >= *t) {
+            return Some(timeout);
+        }
+        if inv.cancel.as_ref().is_some_and(|c| c.is_cancelled()) {
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// [`terminate`] 的异步版本
+#[cfg(feature = "async")]
+#[cfg_attr(not(unix), allow(unused_variables))]
+async fn terminate_async(child: &mut tokio::process::Child, group: bool) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let pid = pid as libc::pid_t;
+        // SAFETY: 只是向进程或进程组发送信号
+        unsafe { libc::kill(if group { -pid } else { pid }, libc::SIGTERM) };
+        let _ = tokio::time::timeout(TERM_GRACE, child.wait()).await;
+        if group {
+            // SAFETY: 同上
+            unsafe { libc::kill(-pid, libc::SIGKILL) };
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// 启动进程失败时，找不到可执行文件转为 [`BrewError::BrewNotFound`]
+fn not_found_or_io(e: io::Error, bin: &Path) -> BrewError {
+    if e.kind() == io::ErrorKind::NotFound {
+        BrewError::BrewNotFound { tried: vec![bin.to_path_buf()] }
+    } else {
+        BrewError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::Brew;
+
+    use super::*;
+
+    /// 在 `target/fake-brew` 下生成一个假的 `brew` 脚本
+    #[cfg(unix)]
+    fn fake_brew(name: &str, body: &str) -> PathBuf {
+        use std::{fs, os::unix::fs::PermissionsExt};
+
+        let dir = std::env::current_dir().unwrap().join("target/fake-brew");
+        fs::create_dir_all(&dir).unwrap();
+        let bin = dir.join(name);
+        fs::write(&bin, format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(&bin, fs::Permissions::from_mode(0o755)).unwrap();
+        bin
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_fake_brew() {
+        let bin = fake_brew("echo-args", r#"echo "$@"; echo "Warning: careful" >&2; exit 0"#);
+        let out = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .args(["install", "my formula"])
+            .run()
+            .unwrap();
+        assert_eq!(out.stdout, "install my formula");
+        assert_eq!(out.warnings, ["careful"]);
+    }
+
+    /// `Future` 被取消后子进程会被杀掉
+    #[cfg(all(feature = "async", target_os = "linux"))]
+    #[tokio::test]
+    async fn test_async_cancel_kills_child() {
+        use std::{fs, time::Duration};
+
+        let pid_file = std::env::current_dir().unwrap().join("target/fake-brew/sleep.pid");
+        let _ = fs::remove_file(&pid_file);
+        let bin = fake_brew("sleep", &format!("echo $$ > {}\nexec sleep 30", pid_file.display()));
+        let mut brew = Brew::default();
+        brew.set_bin(&bin).set_executor(Arc::new(ProcessExecutor)).arg("update");
+
+        let res = tokio::time::timeout(Duration::from_millis(500), brew.run_async()).await;
+        assert!(res.is_err());
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let stat = format!("/proc/{}/stat", pid.trim());
+        for _ in 0..50 {
+            // 进程退出后可能短暂处于僵尸状态
+            match fs::read_to_string(&stat) {
+                Ok(s) if !s.contains(") Z ") => tokio::time::sleep(Duration::from_millis(50)).await,
+                _ => return,
+            }
+        }
+        panic!("brew process {} is still running", pid.trim());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spawn_events() {
+        let bin = fake_brew("events", "echo '==> Pouring wget'\nsleep 0.1\necho 'Warning: careful' >&2\nprintf 'no newline'\nexit 3");
+        let mut events = Vec::new();
+        let code = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .arg("install")
+            .spawn_with(|event| events.push(event.clone()))
+            .unwrap();
+        assert_eq!(code, Some(3));
+        assert_eq!(events, [
+            BrewEvent::stdout("==> Pouring wget"),
+            BrewEvent::stderr("Warning: careful"),
+            BrewEvent::stdout("no newline"),
+        ]);
+
+        let err = Brew::default()
+            .set_bin(fake_brew("fail", "echo 'Error: No such keg: /opt/homebrew/Cellar/gotop' >&2\nexit 1"))
+            .set_executor(Arc::new(ProcessExecutor))
+            .spawn()
+            .unwrap_err();
+        assert!(matches!(err, BrewError::NoSuchKeg { .. }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_env_isolation() {
+        let bin = fake_brew("env", "pwd\nenv");
+        let dir = std::env::current_dir().unwrap().join("target");
+        let mut brew = Brew::default();
+        brew.set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .env_clear()
+            .set_current_dir(&dir);
+        let out = brew.output_vec().unwrap();
+        assert_eq!(out[0], dir.display().to_string());
+        assert!(out.contains(&"HOMEBREW_NO_AUTO_UPDATE=1".to_string()));
+        assert!(out.iter().any(|l| l.starts_with("PATH=")));
+        // cargo 为测试进程设置的变量不会被继承
+        assert!(!out.iter().any(|l| l.starts_with("CARGO_PKG_NAME=")));
+        assert!(!brew.effective_env().contains_key("CARGO_PKG_NAME"));
+
+        let mut brew = Brew::default();
+        brew.set_bin(&bin).set_executor(Arc::new(ProcessExecutor));
+        assert!(brew.output().unwrap().contains("CARGO_PKG_NAME=homebrew"));
+        brew.env_remove("CARGO_PKG_NAME").env_remove("HOMEBREW_NO_AUTO_UPDATE");
+        let out = brew.output().unwrap();
+        assert!(!out.contains("CARGO_PKG_NAME="));
+        assert!(!out.contains("HOMEBREW_NO_AUTO_UPDATE="));
+        assert!(out.contains("PATH="));
+    }
+
+    /// 标准输入默认为空，等待输入的提示会终止命令
+    #[cfg(unix)]
+    #[test]
+    fn test_stdin_and_prompt() {
+        use std::time::Duration;
+
+        let bin = fake_brew("read", "if read answer; then echo \"got $answer\"; else echo eof; fi");
+        let out = Brew::default().set_bin(&bin).set_executor(Arc::new(ProcessExecutor)).output().unwrap();
+        assert_eq!(out, "eof");
+
+        let bin = fake_brew("prompt", "echo '==> Cleaning up'\necho 'Do you want to continue? [y/N]'\nsleep 30");
+        let start = Instant::now();
+        let mut lines = Vec::new();
+        let err = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .arg("cleanup")
+            .spawn_with(|event| lines.push(event.line.clone()))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(matches!(err, BrewError::InteractivePromptDetected { ref prompt } if prompt.ends_with("[y/N]")));
+        assert_eq!(lines, ["==> Cleaning up", "Do you want to continue? [y/N]"]);
+
+        // 没有换行符的提示在输出停顿后同样会被检测到
+        let bin = fake_brew("prompt-partial", "printf 'Password: '; sleep 30");
+        let start = Instant::now();
+        let err = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .spawn_with(|_| {})
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(matches!(err, BrewError::InteractivePromptDetected { ref prompt } if prompt == "Password:"));
+
+        // `caveats` 中间提到 `password:` 不会终止命令
+        let bin = fake_brew("caveats", "echo '==> Caveats'\necho 'The default login is admin, password: changeme'\necho 'Do you want to continue? Run brew services start foo'");
+        let code = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .arg("install")
+            .spawn_with(|_| {})
+            .unwrap();
+        assert_eq!(code, Some(0));
+
+        // 关闭检测后不会终止
+        let code = Brew::default()
+            .set_bin(fake_brew("prompt-quick", "echo 'Password:'"))
+            .set_executor(Arc::new(ProcessExecutor))
+            .set_prompt_detection(false)
+            .spawn_with(|_| {})
+            .unwrap();
+        assert_eq!(code, Some(0));
+    }
+
+    /// 超时和取消都会终止进程并保留已有的输出
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_and_cancel() {
+        use std::time::Duration;
+
+        use crate::CancelHandle;
+
+        let bin = fake_brew("slow", "echo '==> Updating Homebrew...'\necho 'Warning: slow' >&2\nsleep 30");
+        let start = Instant::now();
+        let err = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .arg("update")
+            .set_timeout(Duration::from_millis(300))
+            .run()
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        match err {
+            BrewError::Timeout { timeout, stdout, stderr } => {
+                assert_eq!(timeout, Duration::from_millis(300));
+                assert_eq!(stdout, "==> Updating Homebrew...\n");
+                assert_eq!(stderr, "Warning: slow\n");
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+
+        let handle = CancelHandle::new();
+        let h = handle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            h.cancel();
+        });
+        let mut lines = Vec::new();
+        let err = ProcessExecutor
+            .spawn(
+                &Brew::default().set_bin(&bin).arg("update").set_cancel_handle(&handle).invocation(),
+                &mut |event| lines.push(event.line.clone()),
+            )
+            .unwrap_err();
+        assert!(matches!(err, BrewError::Cancelled { ref stdout, .. } if stdout == "==> Updating Homebrew...\n"));
+        assert_eq!(lines, ["==> Updating Homebrew...", "Warning: slow"]);
+
+        // 没有超时的命令正常返回
+        let out = Brew::default()
+            .set_bin(fake_brew("quick", "echo done"))
+            .set_executor(Arc::new(ProcessExecutor))
+            .set_timeout(Duration::from_secs(10))
+            .run()
+            .unwrap();
+        assert_eq!(out.stdout, "done");
+    }
+
+    #[cfg(all(feature = "async", unix))]
+    #[tokio::test]
+    async fn test_async_timeout() {
+        use std::time::Duration;
+
+        let bin = fake_brew("slow-async", "echo '==> Updating Homebrew...'\nsleep 30");
+        let err = Brew::default()
+            .set_bin(&bin)
+            .set_executor(Arc::new(ProcessExecutor))
+            .arg("update")
+            .set_timeout(Duration::from_millis(300))
+            .run_async()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BrewError::Timeout { ref stdout, .. } if stdout == "==> Updating Homebrew...\n"));
+    }
+
+    /// 只开启提示检测时 `brew` 和调用方在同一个进程组中，设置超时后才使用独立的进程组
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_group() {
+        use std::time::Duration;
+
+        let bin = fake_brew("pgrp", "cut -d' ' -f5 /proc/$$/stat");
+        let pgrp = |brew: &mut Brew| {
+            let mut lines = Vec::new();
+            brew.set_bin(&bin).set_executor(Arc::new(ProcessExecutor)).spawn_with(|e| lines.push(e.line.clone())).unwrap();
+            lines[0].parse::<libc::pid_t>().unwrap()
+        };
+        // SAFETY: 只是读取当前进程组
+        let own = unsafe { libc::getpgrp() };
+        assert_eq!(pgrp(&mut Brew::default()), own);
+        assert_ne!(pgrp(Brew::default().set_timeout(Duration::from_secs(10))), own);
+    }
+
+    #[cfg(all(feature = "async", unix))]
+    #[tokio::test]
+    async fn test_async_partial_prompt() {
+        let bin = fake_brew("prompt-partial-async", "printf 'Password: '; sleep 30");
+        let err = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            Brew::default().set_bin(&bin).set_executor(Arc::new(ProcessExecutor)).spawn_with_async(|_| {}),
+        )
+        .await
+        .unwrap()
+        .unwrap_err();
+        assert!(matches!(err, BrewError::InteractivePromptDetected { ref prompt } if prompt == "Password:"));
+    }
+
+    #[test]
+    fn test_bin_not_found() {
+        let err = Brew::new("--prefix")
+            .set_bin("target/no-such-brew")
+            .set_executor(Arc::new(ProcessExecutor))
+            .run()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BrewError::BrewNotFound { ref tried } if tried == &[PathBuf::from("target/no-such-brew")]
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+/// 等待用户输入的提示常见的结尾，不区分大小写，后面可以再跟一个 `:` 或 `?`
+const ANSWER_ENDINGS: &[&str] = &["[y/n]", "(y/n)", "[yes/no]", "(yes/no)"];
+
+/// 以 `:` 或 `?` 结尾时视为提示的结尾，不区分大小写
+const QUESTION_ENDINGS: &[&str] = &[
+    "password",
+    "passphrase",
+    "press return",
+    "press enter",
+    "press any key",
+    "to continue",
+    "to abort",
+    "do you want to continue",
+    "do you accept",
+    "do you agree",
+];
+
+/// 一行输出是否像是在等待用户输入，是的话返回去掉首尾空白的提示内容
+///
+/// 只有这些内容出现在行尾时才算提示，`caveats` 等日志中间提到 `password:` 不会被误判
+pub(crate) fn detect_prompt(line: &str) -> Option<String> {
+    let lower = line.trim().to_ascii_lowercase();
+    let stripped = lower.strip_suffix([':', '?']).map(str::trim_end);
+    let is_prompt = ANSWER_ENDINGS.iter().any(|p| lower.ends_with(p) || stripped.is_some_and(|s| s.ends_with(p)))
+        || stripped.is_some_and(|s| QUESTION_ENDINGS.iter().any(|p| s.ends_with(p)));
+    is_prompt.then(|| line.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_prompt() {
+        assert_eq!(detect_prompt("Password:").as_deref(), Some("Password:"));
+        assert!(detect_prompt("==> Do you want to continue? [Y/n] ").is_some());
+        assert!(detect_prompt("Press RETURN/ENTER to continue or any other key to abort:").is_some());
+        assert!(detect_prompt("Enter passphrase:").is_some());
+        assert!(detect_prompt("sudo: a password is required").is_none());
+        // 日志中间出现的提示内容不算
+        assert!(detect_prompt("  The default login is admin, password: changeme").is_none());
+        assert!(detect_prompt("Please wait for it to finish or terminate it to continue.").is_none());
+        assert!(detect_prompt("==> Pouring wget--1.25.0.arm64_sequoia.bottle.tar.gz").is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+
+use super::Invocation;
+
+/// 渲染成可以直接粘贴到 shell 中执行的命令
+///
+/// 没有指定 `program` 时显示为 `brew`，参数和环境变量都会按 POSIX shell 的规则加引号
+///
+/// Examples
+///
+/// ```
+/// let inv = homebrew::Brew::new("tap")
+///     .arg("https://example.com/my repo.git")
+///     .set_env("HOMEBREW_NO_ANALYTICS", "1")
+///     .invocation();
+///
+/// assert_eq!(
+///     inv.to_string(),
+///     "HOMEBREW_NO_ANALYTICS=1 brew tap 'https://example.com/my repo.git'",
+/// );
+/// ```
+impl fmt::Display for Invocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words: Vec<String> = Vec::new();
+        if let Some(dir) = &self.current_dir {
+            words.push(format!("cd {} &&", quote(&dir.to_string_lossy())));
+        }
+        if self.env_clear {
+            words.push("env -i".to_string());
+            // 保留的变量从调用者的 shell 中展开
+            for key in &self.env_allow {
+                words.push(format!("{key}=\"${key}\""));
+            }
+        } else if !self.env_remove.is_empty() {
+            words.push("env".to_string());
+            for key in &self.env_remove {
+                words.push(format!("-u {}", quote(key)));
+            }
+        }
+        let mut envs: Vec<(&String, &String)> = self.envs.iter().collect();
+        envs.sort();
+        for (k, v) in envs {
+            words.push(format!("{k}={}", quote(v)));
+        }
+        match &self.program {
+            Some(p) => words.push(quote(&p.to_string_lossy())),
+            None => words.push("brew".to_string()),
+        }
+        for arg in &self.args {
+            words.push(quote(&arg.to_string_lossy()));
+        }
+        write!(f, "{}", words.join(" "))
+    }
+}
+
+/// 按 POSIX shell 的规则加引号，只包含安全字符时原样返回
+pub(crate) fn quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Brew;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(quote("wget"), "wget");
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote("$HOME"), "'$HOME'");
+
+        let inv = Brew::new("install wget")
+            .set_bin("/opt/homebrew/bin/brew")
+            .env_clear()
+            .set_env("HOMEBREW_NO_AUTO_UPDATE", "1")
+            .set_current_dir("/tmp/my dir")
+            .invocation();
+        let text = inv.to_string();
+        assert!(text.starts_with("cd '/tmp/my dir' && env -i PATH=\"$PATH\" HOME=\"$HOME\" "));
+        assert!(text.ends_with(" HOMEBREW_NO_AUTO_UPDATE=1 /opt/homebrew/bin/brew install wget"));
+
+        let inv = Brew::new("config").env_remove("HOMEBREW_PREFIX").invocation();
+        assert_eq!(inv.to_string(), "env -u HOMEBREW_PREFIX brew config");
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BrewError, Result};
+
+use super::{BrewEvent, BrewExecutor, ExecOutput, Invocation, OutputStream, ProcessExecutor};
+
+/// 录制文件中的一行，对应一次 `brew` 调用
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    pub args: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+
+    /// 执行器返回的错误，比如找不到 `brew`、超时，回放时会以 [`BrewError::Io`] 返回
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TranscriptEntry {
+    fn to_output(&self) -> Result<ExecOutput> {
+        if let Some(error) = &self.error {
+            return Err(BrewError::Io(io::Error::other(error.clone())));
+        }
+        Ok(ExecOutput {
+            code: self.exit_code,
+            stdout: self.stdout.clone().into_bytes(),
+            stderr: self.stderr.clone().into_bytes(),
+        })
+    }
+}
+
+/// 录制执行器，把每次调用及其结果以 JSON Lines 格式写入文件
+///
+/// 环境变量中的密钥会被替换为 `***`，见 [`Invocation::redacted_envs`]，执行器返回的错误也会被录制
+///
+/// 真正的执行交给内部的执行器，默认为 [`ProcessExecutor`]，录制的文件可以交给 [`ReplayExecutor`] 回放
+///
+/// Examples
+///
+/// ```ignore
+/// use std::sync::Arc;
+///
+/// use homebrew::RecordingExecutor;
+///
+/// // 在安装了 brew 的机器上录制
+/// let recorder = RecordingExecutor::create("tests/data/brew.jsonl").unwrap();
+/// homebrew::set_default_executor(Arc::new(recorder));
+///
+/// homebrew::info("wget").unwrap();
+/// homebrew::services().unwrap();
+/// homebrew::config().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RecordingExecutor {
+    inner: Arc<dyn BrewExecutor>,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl RecordingExecutor {
+    /// 新建录制文件，已存在时会被清空
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::with_inner(Arc::new(ProcessExecutor), path)
+    }
+
+    /// 使用指定的执行器执行命令并录制
+    pub fn with_inner<P: AsRef<Path>>(inner: Arc<dyn BrewExecutor>, path: P) -> Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self { inner, writer: Mutex::new(BufWriter::new(file)) })
+    }
+
+    /// 写入一行记录
+    fn record(&self, entry: &TranscriptEntry) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_writer(&mut *writer, entry)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn entry(inv: &Invocation, out: &ExecOutput, error: Option<&BrewError>, start: Instant) -> TranscriptEntry {
+        TranscriptEntry {
+            args: inv.args_lossy(),
+            env: inv.redacted_envs(),
+            stdout: String::from_utf8_lossy(&out.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&out.stderr).into_owned(),
+            exit_code: out.code,
+            duration_ms: start.elapsed().as_millis() as u64,
+            error: error.map(BrewError::to_string),
+        }
+    }
+
+    /// 录制 `output` 的结果，返回原来的结果
+    fn record_output(&self, inv: &Invocation, res: Result<ExecOutput>, start: Instant) -> Result<ExecOutput> {
+        let entry = match &res {
+            Ok(out) => Self::entry(inv, out, None, start),
+            Err(e) => Self::entry(inv, &ExecOutput::default(), Some(e), start),
+        };
+        self.record(&entry)?;
+        res
+    }
+
+    /// 录制 `spawn` 的结果，`out` 为已经收到的输出
+    fn record_spawn(&self, inv: &Invocation, mut out: ExecOutput, res: Result<Option<i32>>, start: Instant) -> Result<Option<i32>> {
+        match &res {
+            Ok(code) => {
+                out.code = *code;
+                self.record(&Self::entry(inv, &out, None, start))?;
+            }
+            Err(e) => self.record(&Self::entry(inv, &out, Some(e), start))?,
+        }
+        res
+    }
+}
+
+impl BrewExecutor for RecordingExecutor {
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput> {
+        let start = Instant::now();
+        let res = self.inner.output(inv);
+        self.record_output(inv, res, start)
+    }
+
+    fn spawn(&self, inv: &Invocation, on_event: &mut dyn FnMut(&BrewEvent)) -> Result<Option<i32>> {
+        let start = Instant::now();
+        let mut out = ExecOutput::default();
+        let res = self.inner.spawn(inv, &mut |event| {
+            push_event(&mut out, event);
+            on_event(event);
+        });
+        self.record_spawn(inv, out, res, start)
+    }
+
+    #[cfg(feature = "async")]
+    fn output_async<'a>(&'a self, inv: &'a Invocation) -> super::BoxFuture<'a, Result<ExecOutput>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let res = self.inner.output_async(inv).await;
+            self.record_output(inv, res, start)
+        })
+    }
+
+    #[cfg(feature = "async")]
+    fn spawn_async<'a>(
+        &'a self,
+        inv: &'a Invocation,
+        on_event: &'a mut (dyn FnMut(&BrewEvent) + Send),
+    ) -> super::BoxFuture<'a, Result<Option<i32>>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let mut out = ExecOutput::default();
+            let res = self.inner.spawn_async(inv, &mut |event: &BrewEvent| {
+                push_event(&mut out, event);
+                on_event(event);
+            }).await;
+            self.record_spawn(inv, out, res, start)
+        })
+    }
+}
+
+/// 把实时输出的一行追加到对应的输出中
+fn push_event(out: &mut ExecOutput, event: &BrewEvent) {
+    let buf = match event.stream {
+        OutputStream::Stdout => &mut out.stdout,
+        OutputStream::Stderr => &mut out.stderr,
+    };
+    buf.extend_from_slice(event.line.as_bytes());
+    buf.push(b'\n');
+}
+
+/// 回放执行器，从 [`RecordingExecutor`] 录制的文件中按参数返回结果
+///
+/// 相同参数的多次调用按录制顺序依次返回，用完后一直返回最后一次的结果，没有录制过的调用会以退出码 1 失败
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{MockExecutor, MockResponse, RecordingExecutor, ReplayExecutor};
+///
+/// let path = "target/replay-doc.jsonl";
+/// let mock = MockExecutor::new()
+///     .on(["info", "wget", "--json=v2"], MockResponse::stdout_file("examples/data/wget.json").unwrap());
+/// let recorder = RecordingExecutor::with_inner(Arc::new(mock), path).unwrap();
+/// homebrew::with_executor(Arc::new(recorder), || homebrew::info("wget")).unwrap();
+///
+/// let replay = ReplayExecutor::open(path).unwrap();
+/// let pkg = homebrew::with_executor(Arc::new(replay), || homebrew::info("wget")).unwrap();
+///
+/// assert_eq!(pkg.name, "wget");
+/// ```
+#[derive(Debug)]
+pub struct ReplayExecutor {
+    entries: Mutex<HashMap<Vec<String>, VecDeque<TranscriptEntry>>>,
+}
+
+impl ReplayExecutor {
+    /// 读取录制文件
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        text.parse()
+    }
+}
+
+impl FromStr for ReplayExecutor {
+    type Err = BrewError;
+
+    /// 通过 JSON Lines 字符串新建
+    fn from_str(text: &str) -> Result<Self> {
+        let mut entries: HashMap<Vec<String>, VecDeque<TranscriptEntry>> = HashMap::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: TranscriptEntry = serde_json::from_str(line)
+                .map_err(|e| BrewError::Parse(format!("transcript line {}: {e}", i + 1)))?;
+            entries.entry(entry.args.clone()).or_default().push_back(entry);
+        }
+        Ok(Self { entries: Mutex::new(entries) })
+    }
+}
+
+impl BrewExecutor for ReplayExecutor {
+    fn output(&self, inv: &Invocation) -> Result<ExecOutput> {
+        let args = inv.args_lossy();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let out = match entries.get_mut(&args) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().map(|e| e.to_output()),
+            Some(queue) => queue.front().map(TranscriptEntry::to_output),
+            None => None,
+        };
+        out.unwrap_or_else(|| Ok(ExecOutput {
+            code: Some(1),
+            stdout: Vec::new(),
+            stderr: format!("Error: No recorded response for `brew {}`\n", args.join(" ")).into_bytes(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{with_executor, BrewError, MockExecutor, MockResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = "target/transcript-test.jsonl";
+        let mock = MockExecutor::new()
+            .on(["services", "--json"], MockResponse::stdout_file("examples/data/services.json").unwrap())
+            .on(["config"], MockResponse::stdout_file("examples/data/config.txt").unwrap())
+            .on(["uninstall", "gotop"], MockResponse::failure(1, "Error: No such keg: /opt/homebrew/Cellar/gotop\n"));
+        let recorder = RecordingExecutor::with_inner(Arc::new(mock), path).unwrap();
+        with_executor(Arc::new(recorder), || {
+            crate::services().unwrap();
+            crate::config().unwrap();
+            crate::uninstall("gotop").unwrap_err();
+        });
+
+        let text = fs::read_to_string(path).unwrap();
+        assert_eq!(text.lines().count(), 3);
+        let first: TranscriptEntry = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(first.args, ["services", "--json"]);
+        assert_eq!(first.env.get("HOMEBREW_NO_AUTO_UPDATE").map(String::as_str), Some("1"));
+        assert_eq!(first.exit_code, Some(0));
+
+        let replay = ReplayExecutor::open(path).unwrap();
+        with_executor(Arc::new(replay), || {
+            let srvs = crate::services().unwrap();
+            assert_eq!(srvs.last().unwrap().name, "unbound");
+            let config = crate::config().unwrap();
+            assert_eq!(config.prefix, "/opt/homebrew");
+            let err = crate::uninstall("gotop").unwrap_err();
+            assert!(matches!(err, BrewError::NoSuchKeg { .. }));
+            let err = crate::info("wget").unwrap_err();
+            assert!(matches!(err, BrewError::NonZeroExit { code: Some(1), .. }));
+        });
+    }
+
+    /// 总是返回错误的执行器
+    #[derive(Debug)]
+    struct FailingExecutor;
+
+    impl BrewExecutor for FailingExecutor {
+        fn output(&self, _inv: &Invocation) -> Result<ExecOutput> {
+            Err(BrewError::Io(io::Error::other("brew not found")))
+        }
+    }
+
+    #[test]
+    fn test_record_redacted_and_failed() {
+        let path = "target/transcript-failed-test.jsonl";
+        let recorder = RecordingExecutor::with_inner(Arc::new(FailingExecutor), path).unwrap();
+        with_executor(Arc::new(recorder), || {
+            crate::Brew::new("update")
+                .set_env("HOMEBREW_GITHUB_API_TOKEN", "ghp_xxx")
+                .set_askpass("/Users/me/bin/askpass")
+                .run()
+                .unwrap_err();
+        });
+
+        let text = fs::read_to_string(path).unwrap();
+        assert!(!text.contains("ghp_xxx") && !text.contains("/Users/me"), "{text}");
+        let entry: TranscriptEntry = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(entry.env["HOMEBREW_GITHUB_API_TOKEN"], "***");
+        assert_eq!(entry.env["SUDO_ASKPASS"], "***");
+        assert!(entry.error.as_deref().unwrap().contains("brew not found"));
+
+        let replay = ReplayExecutor::open(path).unwrap();
+        let err = with_executor(Arc::new(replay), || crate::update().unwrap_err());
+        assert!(matches!(err, BrewError::Io(_)));
+    }
+
+    #[test]
+    fn test_replay_in_order() {
+        let text = r#"{"args":["--version"],"env":{},"stdout":"Homebrew 4.4.15\n","stderr":"","exit_code":0,"duration_ms":10}
+{"args":["--version"],"env":{},"stdout":"Homebrew 4.4.16\n","stderr":"","exit_code":0,"duration_ms":10}
+"#;
+        let replay: ReplayExecutor = text.parse().unwrap();
+        with_executor(Arc::new(replay), || {
+            assert_eq!(crate::version().unwrap(), "Homebrew 4.4.15");
+            assert_eq!(crate::version().unwrap(), "Homebrew 4.4.16");
+            assert_eq!(crate::version().unwrap(), "Homebrew 4.4.16");
+        });
+    }
+}
@@ -3,16 +3,58 @@
 //! 命令执行前均默认设置环境变量 `HOMEBREW_NO_AUTO_UPDATE=1` 但 `update()` 除外
 mod model;
 mod config;
+mod env;
+mod mirror;
+mod lock;
+mod metrics;
+mod retry;
+mod trace;
 mod command;
+mod error;
+mod executor;
+
+pub use error::{BrewError, Result};
+pub use config::{get_brew_bin, clear_brew_bin_cache, BREW_BIN_ENV};
+pub use env::{default_env, set_default_env, HomebrewEnv};
+pub use mirror::{MirrorDifference, MirrorProfile};
+pub use lock::{mutation_lock, set_mutation_lock, LockGuard, MutationLock};
+pub use metrics::{metrics_observer, set_metrics_observer, CallMetrics, CommandStats, InMemoryMetrics, MetricsObserver};
+pub use retry::{RetryAttempt, RetryClass, RetryPolicy};
+pub use executor::{
+    current_executor,
+    dry_run,
+    set_default_executor,
+    with_executor,
+    BrewEvent,
+    BrewExecutor,
+    CancelHandle,
+    DryRunExecutor,
+    ExecOutput,
+    Invocation,
+    MockExecutor,
+    MockResponse,
+    OutputStream,
+    ProcessExecutor,
+    RecordingExecutor,
+    ReplayExecutor,
+    StdinMode,
+    TranscriptEntry,
+};
 
 pub use model::formula::Formula;
-pub use model::cask::Cask;
+pub use model::version::BrewVersion;
+pub use model::graph::{DependencyEdge, DependencyGraph, DependencyKind};
+pub use model::autoremove::{Orphan, RemovedKeg};
+pub use model::outdated::Outdated;
+pub use model::uses::Dependent;
+pub use model::cask::{Cask, SudoReason};
 pub use model::config::{Config, Env};
 pub use model::package::Package;
 pub use model::service::{Service, ServiceInfo, ServiceStatus};
-pub use command::info::{info, info_all};
+pub use command::info::{info, info_all, info_installed};
 pub use command::list::{list, list_formulae, list_cask};
-pub use command::cmd::{brew, Brew, brew_spawn};
+pub use command::cmd::{brew, brew_run, Brew, brew_spawn, ENV_ALLOWLIST};
+pub use command::output::BrewOutput;
 pub use command::config::{
     caskroom,
     cache,
@@ -27,17 +69,27 @@ pub use command::config::{
 pub use command::install::{
     update,
     update_spawn,
+    update_spawn_with,
     install,
     install_spawn,
+    install_spawn_with,
     install_cask,
     install_cask_spawn,
     uninstall,
     upgrade,
     upgrade_spawn,
+    upgrade_spawn_with,
     reinstall,
     reinstall_spawn,
+    reinstall_spawn_with,
 };
 pub use command::search::search;
+pub use command::autoremove::{autoremove, autoremove_plan, leaves};
+pub use command::outdated::outdated;
+pub use command::uses::{uses, uses_installed};
+pub use command::progress::{InstallEvent, InstallParser};
+#[cfg(feature = "async")]
+pub use command::asynchronous;
 pub use command::service::{
     services,
     services_run,
@@ -0,0 +1,216 @@
+use std::{
+    env,
+    ffi::OsString,
+    fs::{File, OpenOptions, TryLockError},
+    path::PathBuf,
+    sync::RwLock,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::error::{BrewError, Result};
+use crate::executor::subcommand;
+
+/// 修改系统的 `brew` 命令之间的建议性文件锁，基于 Rust 1.89 稳定的 `File::try_lock`
+///
+/// 开启后 `install`、`uninstall`、`upgrade`、`reinstall`、`update` 和 `services` 的修改类子命令
+/// 会先拿到文件锁再执行，同一台机器上的多个线程和进程会依次执行
+///
+/// `brew` 自身报告 [`BrewError::Locked`] 时（比如有不使用这个锁的 `brew` 进程在运行），会按 `retries` 重试
+///
+/// Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use homebrew::MutationLock;
+///
+/// homebrew::set_mutation_lock(Some(
+///     MutationLock::new("target/brew-doc.lock")
+///         .wait(Duration::from_secs(600))
+///         .retries(3),
+/// ));
+///
+/// let guard = homebrew::mutation_lock().unwrap().acquire().unwrap();
+/// // 锁被占用时等待超时
+/// let err = MutationLock::new("target/brew-doc.lock").wait(Duration::ZERO).acquire().unwrap_err();
+/// assert!(matches!(err, homebrew::BrewError::LockTimeout { .. }));
+/// drop(guard);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationLock {
+    /// 锁文件路径，不存在时会自动创建
+    pub path: PathBuf,
+
+    /// 等待锁的最长时间，为 `None` 时一直等待
+    pub wait: Option<Duration>,
+
+    /// `brew` 报告 [`BrewError::Locked`] 时的重试次数
+    pub retries: u32,
+
+    /// 等待锁和重试的间隔
+    pub retry_interval: Duration,
+}
+
+/// 持有文件锁，离开作用域时释放
+#[derive(Debug)]
+pub struct LockGuard {
+    _file: File,
+}
+
+/// 通过 [`set_mutation_lock`] 设置的全局锁
+static MUTATION_LOCK: RwLock<Option<MutationLock>> = RwLock::new(None);
+
+/// 会修改系统的命令
+const MUTATING_COMMANDS: &[&str] = &[
+    "install", "uninstall", "remove", "rm", "upgrade", "reinstall", "update", "link", "unlink",
+    "pin", "unpin", "tap", "untap", "cleanup", "autoremove",
+];
+
+/// `brew services` 中只读的子命令
+const READONLY_SERVICES: &[&str] = &["list", "info"];
+
+impl Default for MutationLock {
+    fn default() -> Self {
+        Self::new(env::temp_dir().join("homebrew-rs.lock"))
+    }
+}
+
+impl MutationLock {
+    /// 使用指定的锁文件，默认一直等待、不重试
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            wait: None,
+            retries: 0,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// 设置等待锁的最长时间
+    pub fn wait(mut self, wait: Duration) -> Self {
+        self.wait = Some(wait);
+        self
+    }
+
+    /// 设置 `brew` 报告 [`BrewError::Locked`] 时的重试次数
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// 设置等待锁和重试的间隔
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// 获取文件锁，超过 `wait` 后返回 [`BrewError::LockTimeout`]
+    pub fn acquire(&self) -> Result<LockGuard> {
+        let start = Instant::now();
+        loop {
+            if let Some(guard) = self.try_acquire(start)? {
+                return Ok(guard);
+            }
+            thread::sleep(self.poll_interval());
+        }
+    }
+
+    /// [`MutationLock::acquire`] 的异步版本，需要开启 `async` feature
+    #[cfg(feature = "async")]
+    pub async fn acquire_async(&self) -> Result<LockGuard> {
+        let start = Instant::now();
+        loop {
+            if let Some(guard) = self.try_acquire(start)? {
+                return Ok(guard);
+            }
+            tokio::time::sleep(self.poll_interval()).await;
+        }
+    }
+
+    /// 尝试获取一次，被占用时返回 `None`，超时返回错误
+    fn try_acquire(&self, start: Instant) -> Result<Option<LockGuard>> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(&self.path)?;
+        match file.try_lock() {
+            Ok(()) => Ok(Some(LockGuard { _file: file })),
+            Err(TryLockError::WouldBlock) => match self.wait {
+                Some(wait) if start.elapsed() >= wait => {
+                    Err(BrewError::LockTimeout { path: self.path.clone(), wait })
+                }
+                _ => Ok(None),
+            },
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    /// 轮询间隔不超过 100ms，避免等待时间过长
+    fn poll_interval(&self) -> Duration {
+        self.retry_interval.min(Duration::from_millis(100))
+    }
+}
+
+/// 设置全局的文件锁，为 `None` 时关闭
+///
+/// 单个命令可以通过 [`crate::Brew::set_lock`] 设置
+pub fn set_mutation_lock(lock: Option<MutationLock>) {
+    *MUTATION_LOCK.write().unwrap_or_else(|e| e.into_inner()) = lock;
+}
+
+/// 获取通过 [`set_mutation_lock`] 设置的全局锁
+pub fn mutation_lock() -> Option<MutationLock> {
+    MUTATION_LOCK.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 参数对应的命令是否会修改系统
+pub(crate) fn is_mutating(args: &[OsString]) -> bool {
+    match subcommand(args) {
+        Some((cmd, sub)) if cmd == "services" => sub.is_some_and(|sub| !READONLY_SERVICES.contains(&sub.as_str())),
+        Some((cmd, _)) => MUTATING_COMMANDS.contains(&cmd.as_str()),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_is_mutating() {
+        let args = |s: &str| s.split_whitespace().map(OsString::from).collect::<Vec<_>>();
+        assert!(is_mutating(&args("install wget")));
+        assert!(is_mutating(&args("--verbose upgrade")));
+        assert!(is_mutating(&args("services restart unbound")));
+        assert!(!is_mutating(&args("services list")));
+        assert!(!is_mutating(&args("services --json")));
+        assert!(!is_mutating(&args("info wget --json=v2")));
+        assert!(!is_mutating(&args("--prefix")));
+        assert!(is_mutating(&args("-d services restart unbound")));
+    }
+
+    #[test]
+    fn test_serialize() {
+        let lock = MutationLock::new("target/lock-test/brew.lock");
+        let running = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4).map(|_| {
+            let lock = lock.clone();
+            let running = running.clone();
+            thread::spawn(move || {
+                let _guard = lock.acquire().unwrap();
+                assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+            })
+        }).collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+    }
+}
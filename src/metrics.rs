@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fmt,
+    sync::{Arc, Mutex, RwLock},
+    time::Duration,
+};
+
+use crate::executor::subcommand;
+
+/// 一次 [`crate::Brew`] 调用的统计信息，重试的多次执行算作一次调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallMetrics {
+    /// 命令类型，为第一个参数，比如 `info`、`update`，`services` 会带上子命令，比如 `services restart`
+    pub command: String,
+
+    /// 从开始执行到返回的总耗时，包括等待文件锁和重试
+    pub duration: Duration,
+
+    /// 最后一次执行的退出码，没有执行或被信号终止时为 `None`
+    pub code: Option<i32>,
+
+    /// 失败时错误的类型，见 [`crate::BrewError::kind`]
+    pub error: Option<&'static str>,
+
+    /// 执行的次数，包括重试
+    pub attempts: u32,
+}
+
+impl CallMetrics {
+    /// 是否执行成功
+    pub fn success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// 接收每次调用统计信息的观察者
+///
+/// 通过 [`set_metrics_observer`] 全局设置，或通过 [`crate::Brew::set_metrics_observer`] 单独设置
+pub trait MetricsObserver: Send + Sync + fmt::Debug {
+    /// 调用结束后调用，会在执行命令的线程中同步执行，不要在这里阻塞
+    fn record(&self, metrics: &CallMetrics);
+}
+
+/// 单个命令类型的汇总数据
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CommandStats {
+    /// 调用次数
+    pub count: usize,
+
+    /// 失败次数
+    pub failures: usize,
+
+    /// 总耗时
+    pub total: Duration,
+
+    /// 最长耗时
+    pub max: Duration,
+
+    /// 每种错误类型的次数
+    pub errors: BTreeMap<&'static str, usize>,
+}
+
+impl CommandStats {
+    /// 平均耗时
+    pub fn mean(&self) -> Duration {
+        match self.count {
+            0 => Duration::ZERO,
+            n => self.total / n as u32,
+        }
+    }
+}
+
+/// 在内存中保存全部统计信息的观察者，适用于测试和简单的汇总
+///
+/// Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use homebrew::{Brew, InMemoryMetrics, MockExecutor, MockResponse};
+///
+/// let metrics = Arc::new(InMemoryMetrics::new());
+/// let mock = Arc::new(MockExecutor::new()
+///     .on(["--prefix"], MockResponse::stdout("/opt/homebrew\n"))
+///     .on(["uninstall", "gotop"], MockResponse::failure(1, "Error: No such keg: /opt/homebrew/Cellar/gotop\n")));
+///
+/// homebrew::with_executor(mock, || {
+///     Brew::new("--prefix").set_metrics_observer(metrics.clone()).run().unwrap();
+///     Brew::new("uninstall gotop").set_metrics_observer(metrics.clone()).run().unwrap_err();
+/// });
+///
+/// let summary = metrics.summary();
+/// assert_eq!(summary["--prefix"].count, 1);
+/// assert_eq!(summary["uninstall"].failures, 1);
+/// assert_eq!(summary["uninstall"].errors["no_such_keg"], 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemoryMetrics {
+    records: Mutex<Vec<CallMetrics>>,
+}
+
+impl InMemoryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 全部统计信息，按调用顺序排列
+    pub fn records(&self) -> Vec<CallMetrics> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 按命令类型汇总
+    pub fn summary(&self) -> BTreeMap<String, CommandStats> {
+        let mut summary: BTreeMap<String, CommandStats> = BTreeMap::new();
+        for m in self.records.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let stats = summary.entry(m.command.clone()).or_default();
+            stats.count += 1;
+            stats.total += m.duration;
+            stats.max = stats.max.max(m.duration);
+            if let Some(error) = m.error {
+                stats.failures += 1;
+                *stats.errors.entry(error).or_default() += 1;
+            }
+        }
+        summary
+    }
+
+    /// 清空统计信息
+    pub fn clear(&self) {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl MetricsObserver for InMemoryMetrics {
+    fn record(&self, metrics: &CallMetrics) {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).push(metrics.clone());
+    }
+}
+
+static METRICS_OBSERVER: RwLock<Option<Arc<dyn MetricsObserver>>> = RwLock::new(None);
+
+/// 设置全局的观察者，为 `None` 时关闭
+///
+/// 单个命令可以通过 [`crate::Brew::set_metrics_observer`] 设置
+pub fn set_metrics_observer(observer: Option<Arc<dyn MetricsObserver>>) {
+    *METRICS_OBSERVER.write().unwrap_or_else(|e| e.into_inner()) = observer;
+}
+
+/// 获取通过 [`set_metrics_observer`] 设置的全局观察者
+pub fn metrics_observer() -> Option<Arc<dyn MetricsObserver>> {
+    METRICS_OBSERVER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 参数对应的命令类型，跳过 `--verbose` 等选项，只有选项时为第一个选项，比如 `--prefix`
+pub(crate) fn command_kind(args: &[OsString]) -> String {
+    match subcommand(args) {
+        Some((cmd, Some(sub))) => format!("{cmd} {sub}"),
+        Some((cmd, None)) => cmd,
+        None => args.first().map(|a| a.to_string_lossy().into_owned()).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_kind() {
+        let kind = |s: &str| command_kind(&s.split_whitespace().map(OsString::from).collect::<Vec<_>>());
+        assert_eq!(kind("info wget --json=v2"), "info");
+        assert_eq!(kind("services --json list"), "services list");
+        assert_eq!(kind("services"), "services");
+        assert_eq!(kind("--prefix"), "--prefix");
+        assert_eq!(kind("--verbose upgrade wget"), "upgrade");
+        assert_eq!(kind("-d services restart unbound"), "services restart");
+        assert_eq!(kind(""), "");
+    }
+}
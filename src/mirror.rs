@@ -0,0 +1,247 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BrewError, Result};
+use crate::executor::shell::quote;
+use crate::{Config, HomebrewEnv};
+
+/// Homebrew 镜像配置，包括 API、Bottles 和两个 git 仓库的地址
+///
+/// 内置了 `official`、`tuna`、`ustc` 和 `aliyun`，其他镜像可以通过 [`MirrorProfile::custom`] 新建
+///
+/// 通过 [`crate::Brew::set_mirror`] 应用到命令上，git 仓库地址需要执行一次 `brew update` 才会切换
+///
+/// Examples
+///
+/// ```
+/// use homebrew::{Config, MirrorProfile};
+///
+/// let tuna: MirrorProfile = "tuna".parse().unwrap();
+/// let config = Config::from(&std::fs::read_to_string("examples/data/config.txt").unwrap()).unwrap();
+///
+/// assert!(tuna.matches(&config));
+/// assert_eq!(MirrorProfile::detect(&config).unwrap().name, "tuna");
+/// assert_eq!(MirrorProfile::ustc().differences(&config).len(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorProfile {
+    pub name: String,
+
+    /// `HOMEBREW_API_DOMAIN`
+    pub api_domain: String,
+
+    /// `HOMEBREW_BOTTLE_DOMAIN`
+    pub bottle_domain: String,
+
+    /// `HOMEBREW_BREW_GIT_REMOTE`
+    pub brew_git_remote: String,
+
+    /// `HOMEBREW_CORE_GIT_REMOTE`
+    pub core_git_remote: String,
+}
+
+/// [`MirrorProfile::differences`] 中的一项，`actual` 为 `Config` 中的值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorDifference {
+    pub key: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl MirrorProfile {
+    /// 官方源，`brew config` 中没有对应的配置项时视为官方源
+    pub fn official() -> Self {
+        Self::custom(
+            "official",
+            "https://formulae.brew.sh/api",
+            "https://ghcr.io/v2/homebrew/core",
+            "https://github.com/Homebrew/brew",
+            "https://github.com/Homebrew/homebrew-core",
+        )
+    }
+
+    /// 清华大学镜像
+    pub fn tuna() -> Self {
+        Self::custom(
+            "tuna",
+            "https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles/api",
+            "https://mirrors.tuna.tsinghua.edu.cn/homebrew-bottles",
+            "https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/brew.git",
+            "https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/homebrew-core.git",
+        )
+    }
+
+    /// 中国科学技术大学镜像
+    pub fn ustc() -> Self {
+        Self::custom(
+            "ustc",
+            "https://mirrors.ustc.edu.cn/homebrew-bottles/api",
+            "https://mirrors.ustc.edu.cn/homebrew-bottles",
+            "https://mirrors.ustc.edu.cn/brew.git",
+            "https://mirrors.ustc.edu.cn/homebrew-core.git",
+        )
+    }
+
+    /// 阿里云镜像
+    pub fn aliyun() -> Self {
+        Self::custom(
+            "aliyun",
+            "https://mirrors.aliyun.com/homebrew-bottles/api",
+            "https://mirrors.aliyun.com/homebrew/homebrew-bottles",
+            "https://mirrors.aliyun.com/homebrew/brew.git",
+            "https://mirrors.aliyun.com/homebrew/homebrew-core.git",
+        )
+    }
+
+    /// 自定义镜像
+    pub fn custom<N, A, B, G, C>(name: N, api_domain: A, bottle_domain: B, brew_git_remote: G, core_git_remote: C) -> Self
+        where N: Into<String>, A: Into<String>, B: Into<String>, G: Into<String>, C: Into<String>
+    {
+        Self {
+            name: name.into(),
+            api_domain: api_domain.into(),
+            bottle_domain: bottle_domain.into(),
+            brew_git_remote: brew_git_remote.into(),
+            core_git_remote: core_git_remote.into(),
+        }
+    }
+
+    /// 所有内置的镜像
+    pub fn builtin() -> Vec<Self> {
+        vec![Self::official(), Self::tuna(), Self::ustc(), Self::aliyun()]
+    }
+
+    /// 根据 `brew config` 判断当前使用的是哪个内置镜像
+    pub fn detect(config: &Config) -> Option<Self> {
+        Self::builtin().into_iter().find(|m| m.matches(config))
+    }
+
+    /// 转换成 [`HomebrewEnv`]
+    pub fn to_env(&self) -> HomebrewEnv {
+        HomebrewEnv::new()
+            .api_domain(&self.api_domain)
+            .bottle_domain(&self.bottle_domain)
+            .brew_git_remote(&self.brew_git_remote)
+            .core_git_remote(&self.core_git_remote)
+    }
+
+    /// `brew config` 中的配置是否和镜像一致
+    pub fn matches(&self, config: &Config) -> bool {
+        self.differences(config).is_empty()
+    }
+
+    /// 列出 `brew config` 中和镜像不一致的配置项
+    ///
+    /// 比较时忽略末尾的 `/` 和 `.git`，官方源允许配置项为空
+    pub fn differences(&self, config: &Config) -> Vec<MirrorDifference> {
+        let official = Self::official();
+        let pairs = [
+            ("HOMEBREW_API_DOMAIN", &self.api_domain, &config.api_domain, &official.api_domain),
+            ("HOMEBREW_BOTTLE_DOMAIN", &self.bottle_domain, &config.bottle_domain, &official.bottle_domain),
+            ("HOMEBREW_BREW_GIT_REMOTE", &self.brew_git_remote, &config.brew_git_remote, &official.brew_git_remote),
+            ("HOMEBREW_CORE_GIT_REMOTE", &self.core_git_remote, &config.core_git_remote, &official.core_git_remote),
+        ];
+        pairs.into_iter()
+            .filter(|(_, expected, actual, default)| {
+                let actual = if actual.is_empty() { default.as_str() } else { actual.as_str() };
+                normalize(expected) != normalize(actual)
+            })
+            .map(|(key, expected, actual, _)| MirrorDifference {
+                key,
+                expected: expected.clone(),
+                actual: actual.clone(),
+            })
+            .collect()
+    }
+
+    /// 生成 shell 的 `export` 语句，可以写入 `~/.zshrc` 等配置文件
+    ///
+    /// 值会按 POSIX shell 的规则加引号，包含 `"`、`$` 等字符时也可以安全地 `eval`
+    pub fn shell_exports(&self) -> String {
+        self.to_env().vars().iter()
+            .map(|(k, v)| format!("export {k}={}\n", quote(v)))
+            .collect()
+    }
+
+    /// 以 JSON 格式保存到文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 读取 [`MirrorProfile::save`] 保存的文件
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+impl FromStr for MirrorProfile {
+    type Err = BrewError;
+
+    /// 通过名称获取内置的镜像
+    fn from_str(name: &str) -> Result<Self> {
+        Self::builtin().into_iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| BrewError::Parse(format!("unknown mirror profile: {name}")))
+    }
+}
+
+impl fmt::Display for MirrorProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+fn normalize(url: &str) -> &str {
+    let url = url.trim().trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Brew;
+
+    use super::*;
+
+    #[test]
+    fn test_mirror() {
+        let config = Config::default();
+        assert!(MirrorProfile::official().matches(&config));
+        assert!(!MirrorProfile::tuna().matches(&config));
+
+        let config = Config {
+            brew_git_remote: "https://github.com/Homebrew/brew.git".to_string(),
+            bottle_domain: "https://mirrors.ustc.edu.cn/homebrew-bottles/".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(MirrorProfile::official().differences(&config), [MirrorDifference {
+            key: "HOMEBREW_BOTTLE_DOMAIN",
+            expected: "https://ghcr.io/v2/homebrew/core".to_string(),
+            actual: "https://mirrors.ustc.edu.cn/homebrew-bottles/".to_string(),
+        }]);
+        assert!(MirrorProfile::detect(&config).is_none());
+
+        let inv = Brew::default().set_mirror(&MirrorProfile::aliyun()).invocation();
+        assert_eq!(inv.envs["HOMEBREW_BOTTLE_DOMAIN"], "https://mirrors.aliyun.com/homebrew/homebrew-bottles");
+        assert!("gitee".parse::<MirrorProfile>().is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = "target/mirror-test.json";
+        let mirror = MirrorProfile::custom(
+            "company",
+            "https://brew.example.com/api",
+            "https://brew.example.com/bottles",
+            "https://git.example.com/brew.git",
+            "https://git.example.com/homebrew-core.git",
+        );
+        mirror.save(path).unwrap();
+        assert_eq!(MirrorProfile::load(path).unwrap(), mirror);
+        assert!(mirror.shell_exports().contains("export HOMEBREW_API_DOMAIN=https://brew.example.com/api\n"));
+
+        let evil = MirrorProfile::custom("evil", "https://x/\"$(touch pwned)`id`'", "b", "c", "d");
+        assert!(evil.shell_exports().contains(r#"export HOMEBREW_API_DOMAIN='https://x/"$(touch pwned)`id`'\''"#));
+    }
+}
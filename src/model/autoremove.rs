@@ -0,0 +1,246 @@
+use std::{collections::{BTreeMap, BTreeSet, VecDeque}, fmt, path::PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::command::progress::parse_size;
+use crate::{BrewVersion, DependencyGraph, DependencyKind, Formula, Package};
+
+use super::graph::short_name;
+
+/// 可以自动删除的 `Formula`，由 [`Package::autoremove_plan`] 得到
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Orphan {
+    pub name: String,
+    pub full_name: String,
+
+    /// 已安装的版本
+    pub versions: Vec<BrewVersion>,
+
+    /// 仍然依赖它的已安装包，这些包也都会被删除
+    pub dependents: Vec<String>,
+}
+
+impl Orphan {
+    /// 为什么可以删除
+    pub fn explanation(&self) -> String {
+        if self.dependents.is_empty() {
+            format!("{} was installed as a dependency and no installed formula requires it", self.name)
+        } else {
+            format!(
+                "{} was installed as a dependency and is only required by formulae that will also be removed: {}",
+                self.name,
+                self.dependents.join(", "),
+            )
+        }
+    }
+}
+
+impl fmt::Display for Orphan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.explanation())
+    }
+}
+
+/// `brew autoremove` 删除的 keg，从 `Uninstalling /opt/homebrew/Cellar/libidn2/2.3.7... (80 files, 1MB)` 中解析
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovedKeg {
+    pub name: String,
+    pub version: String,
+    pub keg: PathBuf,
+    pub files: u64,
+
+    /// 原始的大小文本，如 `1MB`
+    pub size: String,
+}
+
+impl RemovedKeg {
+    /// 解析一行 `Uninstalling` 输出，不是的话返回 `None`
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix("Uninstalling ")?;
+        let (keg, stats) = rest.split_once("... (")?;
+        let (files, size) = stats.strip_suffix(')')?.split_once(" files, ")?;
+        let keg = PathBuf::from(keg);
+        Some(Self {
+            name: keg.parent()?.file_name()?.to_str()?.to_string(),
+            version: keg.file_name()?.to_str()?.to_string(),
+            keg,
+            files: files.trim().parse().ok()?,
+            size: size.trim().to_string(),
+        })
+    }
+
+    /// 按 `brew` 的规则以 1024 为单位换算成字节
+    pub fn bytes(&self) -> Option<u64> {
+        parse_size(&self.size)
+    }
+}
+
+impl Package {
+    /// 离线计算没有被其他已安装 `Formula` 依赖的包，相当于 `brew leaves [--installed-on-request]`
+    ///
+    /// 只考虑运行时依赖 [`DependencyKind::RUNTIME`]，已安装 `Cask` 依赖的 `Formula` 也不算，
+    /// `installed_on_request` 为 `true` 时只返回用户主动安装的包，结果按名称排序
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::Package;
+    ///
+    /// let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+    /// let pkg = Package::from_all(&json_str).unwrap();
+    ///
+    /// assert_eq!(pkg.leaves(false), ["curl", "pcre2", "tree", "wget"]);
+    /// assert_eq!(pkg.leaves(true), ["curl", "tree", "wget"]);
+    /// ```
+    pub fn leaves(&self, installed_on_request: bool) -> Vec<String> {
+        let installed: Vec<&Formula> = self.formulae().iter().filter(|f| f.is_installed()).collect();
+        let graph = DependencyGraph::from_formulae(installed.iter().copied());
+        let cask_deps = self.cask_dependencies();
+        installed.into_iter()
+            .filter(|f| !installed_on_request || on_request(f))
+            .map(|f| short_name(&f.name))
+            .filter(|name| !cask_deps.contains(name))
+            .filter(|name| !graph.dependents(name).iter().any(|e| e.kind.is_runtime()))
+            .map(String::from)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// 离线计算可以自动删除的 `Formula`，相当于 `brew autoremove --dry-run`
+    ///
+    /// 用户主动安装的包、已安装 `Cask` 依赖的 `Formula` 和它们的运行时依赖都会保留，从源码编译的包还会保留构建依赖，
+    /// 剩下的已安装包就是可以删除的，结果按名称排序，每一项都可以通过 [`Orphan::explanation`] 查看原因
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::Package;
+    ///
+    /// let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+    /// let pkg = Package::from_all(&json_str).unwrap();
+    ///
+    /// let plan = pkg.autoremove_plan();
+    /// assert_eq!(plan.len(), 1);
+    /// assert_eq!(plan[0].name, "pcre2");
+    /// assert_eq!(plan[0].versions[0].as_str(), "10.44");
+    /// assert_eq!(plan[0].to_string(), "pcre2 was installed as a dependency and no installed formula requires it");
+    /// ```
+    pub fn autoremove_plan(&self) -> Vec<Orphan> {
+        let installed: BTreeMap<&str, &Formula> = self.formulae().iter()
+            .filter(|f| f.is_installed())
+            .map(|f| (short_name(&f.name), f))
+            .collect();
+        let graph = DependencyGraph::from_formulae(installed.values().copied());
+
+        let mut kept: BTreeSet<String> = BTreeSet::new();
+        let mut queue: VecDeque<String> = installed.iter()
+            .filter(|(_, f)| on_request(f))
+            .map(|(name, _)| *name)
+            .chain(self.cask_dependencies())
+            .map(String::from)
+            .collect();
+        while let Some(name) = queue.pop_front() {
+            if kept.contains(&name) {
+                continue;
+            }
+            let from_source = installed.get(name.as_str())
+                .is_some_and(|f| f.installed.iter().any(|i| !i.poured_from_bottle));
+            for edge in graph.dependencies(&name) {
+                if edge.kind.is_runtime() || (from_source && edge.kind == DependencyKind::Build) {
+                    queue.push_back(edge.to);
+                }
+            }
+            kept.insert(name);
+        }
+
+        installed.iter()
+            .filter(|(name, _)| !kept.contains(**name))
+            .map(|(name, f)| Orphan {
+                name: name.to_string(),
+                full_name: f.full_name.clone(),
+                versions: f.installed.iter().map(|i| i.version.clone()).collect(),
+                dependents: graph.dependents(name).iter()
+                    .filter(|e| e.kind.is_runtime())
+                    .map(|e| e.from.clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// 已安装 `Cask` 直接依赖的 `Formula` 的短名称
+    fn cask_dependencies(&self) -> BTreeSet<&str> {
+        self.casks().iter()
+            .filter(|c| c.installed.is_some())
+            .flat_map(|c| c.depends_on.formula.iter().map(|f| short_name(f)))
+            .collect()
+    }
+}
+
+/// 是否为用户主动安装
+fn on_request(formula: &Formula) -> bool {
+    formula.installed.iter().any(|i| i.installed_on_request)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_autoremove_plan() {
+        let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+        for f in json["formulae"].as_array_mut().unwrap() {
+            if f["name"] == "curl" || f["name"] == "wget" {
+                f["installed"][0]["installed_on_request"] = false.into();
+                f["installed"][0]["installed_as_dependency"] = true.into();
+            }
+        }
+        let pkg = Package::from_all(&json.to_string()).unwrap();
+
+        let plan = pkg.autoremove_plan();
+        let names: Vec<_> = plan.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["ca-certificates", "curl", "libidn2", "libunistring", "openssl@3", "pcre2", "wget"]);
+
+        let libidn2 = &plan[2];
+        assert_eq!(libidn2.dependents, ["curl", "wget"]);
+        assert!(libidn2.explanation().ends_with("will also be removed: curl, wget"));
+        assert_eq!(pkg.leaves(true), ["tree"]);
+    }
+
+    /// 已安装 `Cask` 依赖的 `Formula` 不会被删除，也不算 `leaves`
+    #[test]
+    fn test_autoremove_keeps_cask_dependencies() {
+        let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+        for c in json["casks"].as_array_mut().unwrap() {
+            if c["token"] == "kitty" {
+                c["depends_on"]["formula"] = serde_json::json!(["pcre2"]);
+            }
+        }
+        let pkg = Package::from_all(&json.to_string()).unwrap();
+
+        let kitty = pkg.casks().iter().find(|c| c.token == "kitty").unwrap();
+        assert_eq!(kitty.depends_on.formula, ["pcre2"]);
+        assert!(pkg.autoremove_plan().is_empty());
+        assert_eq!(pkg.leaves(false), ["curl", "tree", "wget"]);
+    }
+
+    #[test]
+    fn test_removed_keg() {
+        let keg = RemovedKeg::parse("Uninstalling /opt/homebrew/Cellar/libidn2/2.3.7... (80 files, 1MB)").unwrap();
+        assert_eq!(keg.name, "libidn2");
+        assert_eq!(keg.version, "2.3.7");
+        assert_eq!(keg.files, 80);
+        assert_eq!(keg.bytes(), Some(1 << 20));
+        assert!(RemovedKeg::parse("==> Autoremoving 1 unneeded formula:").is_none());
+    }
+}
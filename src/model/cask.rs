@@ -1,6 +1,8 @@
 use serde::{Serialize, Deserialize};
 use std::fmt;
 
+use crate::BrewVersion;
+
 /// `Cask` 包的结构体
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Cask {
@@ -13,8 +15,8 @@ pub struct Cask {
     pub homepage: String,
     pub url: String,
     pub url_specs: UrlSpecs,
-    pub version: String,
-    pub installed: Option<String>,
+    pub version: BrewVersion,
+    pub installed: Option<BrewVersion>,
     pub installed_time: Option<u64>,
     pub bundle_version: Option<String>,
     pub bundle_short_version: Option<String>,
@@ -45,6 +47,106 @@ impl Cask {
     pub fn is_installed(&self) -> bool {
         self.installed.is_some()
     }
+
+    /// 安装时需要 `sudo` 或用户交互的原因，为空时不需要输入密码，也不需要手动操作
+    ///
+    /// `upgrade` 和 `reinstall` 会先卸载旧版本，还需要检查 [`Cask::uninstall_sudo_reasons`]
+    ///
+    /// 没有终端时可以通过 [`crate::Brew::set_askpass`] 提供密码
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::{Cask, SudoReason};
+    ///
+    /// let json_str = fs::read_to_string("examples/data/kitty.json").unwrap();
+    /// let mut value: serde_json::Value = serde_json::from_str(&json_str).unwrap();
+    /// let mut value = value["casks"][0].take();
+    /// value["artifacts"] = serde_json::json!([
+    ///     {"pkg": ["zoomusInstallerFull.pkg"]},
+    ///     {"uninstall": [{"launchctl": "us.zoom.ZoomDaemon", "pkgutil": ["us.zoom.pkg.videomeeting"]}]}
+    /// ]);
+    /// let cask: Cask = serde_json::from_value(value).unwrap();
+    ///
+    /// assert!(cask.requires_sudo());
+    /// assert_eq!(cask.install_sudo_reasons(), [SudoReason::Pkg("zoomusInstallerFull.pkg".to_string())]);
+    /// assert_eq!(cask.uninstall_sudo_reasons(), [
+    ///     SudoReason::Launchctl("us.zoom.ZoomDaemon".to_string()),
+    ///     SudoReason::Pkgutil("us.zoom.pkg.videomeeting".to_string()),
+    /// ]);
+    /// ```
+    pub fn install_sudo_reasons(&self) -> Vec<SudoReason> {
+        let mut reasons = Vec::new();
+        for artifact in &self.artifacts {
+            for pkg in artifact.pkg.iter().flatten() {
+                if let Pkg::String(path) = pkg {
+                    reasons.push(SudoReason::Pkg(path.clone()));
+                }
+            }
+            for installer in artifact.installer.iter().flatten() {
+                if let Some(script) = installer.script.as_ref().filter(|s| s.sudo == Some(true)) {
+                    reasons.push(SudoReason::InstallerScript(script.executable.clone().unwrap_or_default()));
+                }
+                if let Some(manual) = &installer.manual {
+                    reasons.push(SudoReason::ManualInstaller(manual.clone()));
+                }
+            }
+        }
+        reasons
+    }
+
+    /// 卸载时需要 `sudo` 的原因，`launchctl` 只有系统级的服务才需要
+    pub fn uninstall_sudo_reasons(&self) -> Vec<SudoReason> {
+        let mut reasons = Vec::new();
+        for artifact in &self.artifacts {
+            for uninstall in artifact.uninstall.iter().flatten() {
+                let Uninstall::Struct(u) = uninstall else { continue };
+                reasons.extend(u.launchctl.iter().flat_map(Quit::to_vec).map(SudoReason::Launchctl));
+                reasons.extend(u.kext.iter().flat_map(Quit::to_vec).map(SudoReason::Kext));
+                reasons.extend(u.pkgutil.iter().flat_map(Quit::to_vec).map(SudoReason::Pkgutil));
+                if let Some(Script::Struct(script)) = u.script.as_ref().filter(|s| s.sudo()) {
+                    reasons.push(SudoReason::UninstallScript(script.executable.clone().unwrap_or_default()));
+                }
+                reasons.extend(u.delete.iter().flat_map(Quit::to_vec).map(SudoReason::Delete));
+            }
+        }
+        reasons
+    }
+
+    /// 安装时是否需要 `sudo` 或用户交互，见 [`Cask::install_sudo_reasons`]
+    pub fn requires_sudo(&self) -> bool {
+        !self.install_sudo_reasons().is_empty()
+    }
+}
+
+/// `Cask` 安装或卸载时需要 `sudo` 或用户交互的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SudoReason {
+    /// 通过 `installer` 安装 `.pkg` 包
+    Pkg(String),
+
+    /// 安装脚本设置了 `sudo: true`，为脚本路径
+    InstallerScript(String),
+
+    /// 需要用户手动运行的安装程序，`brew` 只会提示安装程序的路径，为安装程序路径
+    ManualInstaller(String),
+
+    /// 卸载系统级的 `launchctl` 服务
+    Launchctl(String),
+
+    /// 卸载内核扩展
+    Kext(String),
+
+    /// 通过 `pkgutil` 删除 `.pkg` 安装的文件
+    Pkgutil(String),
+
+    /// 卸载脚本设置了 `sudo: true`，为脚本路径
+    UninstallScript(String),
+
+    /// 删除系统目录中的文件
+    Delete(String),
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -70,7 +172,8 @@ pub struct Artifact {
     pub preflight: Option<String>,
     pub app: Option<Vec<App>>,
     pub uninstall: Option<Vec<Uninstall>>,
-    pub installer: Option<Vec<Uninstall>>,
+    pub installer: Option<Vec<Installer>>,
+    pub pkg: Option<Vec<Pkg>>,
     pub binary: Option<Vec<Binary>>,
     pub zap: Option<Vec<Zap>>,
 }
@@ -78,6 +181,7 @@ pub struct Artifact {
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Installer {
     pub script: Option<InstallerScript>,
+    pub manual: Option<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -87,6 +191,19 @@ pub struct InstallerScript {
     pub sudo: Option<bool>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)] // 允许根据 JSON 的结构选择合适的变体
+pub enum Pkg {
+    Struct(PkgStruct),
+    String(String),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct PkgStruct {
+    pub allow_untrusted: Option<bool>,
+    pub choices: Option<Vec<serde_json::Value>>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)] // 允许根据 JSON 的结构选择合适的变体
 pub enum Uninstall {
@@ -98,6 +215,24 @@ pub enum Uninstall {
 pub struct UninstallStruct {
     pub quit: Option<Quit>,
     pub delete: Option<Quit>,
+    pub launchctl: Option<Quit>,
+    pub pkgutil: Option<Quit>,
+    pub kext: Option<Quit>,
+    pub script: Option<Script>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)] // 允许根据 JSON 的结构选择合适的变体
+pub enum Script {
+    Struct(InstallerScript),
+    String(String),
+}
+
+impl Script {
+    /// 是否通过 `sudo` 执行
+    pub fn sudo(&self) -> bool {
+        matches!(self, Script::Struct(s) if s.sudo == Some(true))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -107,6 +242,16 @@ pub enum Quit {
     String(String),
 }
 
+impl Quit {
+    /// 统一转为列表
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            Quit::Vec(v) => v.clone(),
+            Quit::String(s) => vec![s.clone()],
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)] // 允许根据 JSON 的结构选择合适的变体
 pub enum App {
@@ -147,6 +292,10 @@ pub enum Trash {
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct DependsOn {
     pub macos: Option<MacOS>,
+
+    /// 依赖的 `Formula`
+    #[serde(default)]
+    pub formula: Vec<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -242,11 +391,14 @@ mod tests {
     fn test_all_to_cask() {
         let tempfile = PathBuf::from("target/package.json");
         if !tempfile.exists() {
-            let pkg = info_all().unwrap();
-            pkg.to_file(&tempfile).unwrap();
+            if let Ok(pkg) = info_all() {
+                pkg.to_file(&tempfile).unwrap();
+            }
         }
+        // 没有安装 `brew` 的机器上使用示例数据
+        let path = if tempfile.exists() { tempfile } else { PathBuf::from("examples/data/all.json") };
 
-        let out = fs::read_to_string(&tempfile).unwrap();
+        let out = fs::read_to_string(&path).unwrap();
         let all_packages: HashMap<String, Value> = serde_json::from_str(&out).unwrap();
 
         for (key, value) in &all_packages {
@@ -257,7 +409,7 @@ mod tests {
             let packages: Vec<Value> = value.as_array().unwrap().to_vec();
             for val in &packages {
                 let name = &val["token"];
-                let pkg_m = serde_json::from_value::<Cask>(val.clone()).map_err(|e| anyhow::Error::new(e));
+                let pkg_m = serde_json::from_value::<Cask>(val.clone()).map_err(anyhow::Error::new);
                 match pkg_m{
                     Ok(pkg) => assert_eq!(&pkg.token, name),
                     Err(e) => {
@@ -272,4 +424,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_manual_installer() {
+        let json_str = fs::read_to_string("examples/data/kitty.json").unwrap();
+        let mut value: Value = serde_json::from_str(&json_str).unwrap();
+        let mut value = value["casks"][0].take();
+        value["artifacts"] = serde_json::json!([{"installer": [{"manual": "Install Little Snitch.app"}]}]);
+        let cask: Cask = serde_json::from_value(value).unwrap();
+
+        assert!(cask.requires_sudo());
+        assert_eq!(cask.install_sudo_reasons(), [SudoReason::ManualInstaller("Install Little Snitch.app".to_string())]);
+    }
+
 }
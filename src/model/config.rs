@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 
+use crate::trace::from_json;
+
 /// `Config` 的结构体
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Config {
@@ -163,7 +165,7 @@ impl Config {
         }
 
         let json_str = serde_json::to_string(&data)?;
-        let config: Self = serde_json::from_str(&json_str)?;
+        let config: Self = from_json("Config", &json_str)?;
 
         Ok(config)
     }
@@ -256,7 +258,7 @@ impl Env {
             data.insert(kv[0].clone(), Value::String(kv[1].clone()));
         }
 
-        let env: Self = serde_json::from_value(serde_json::to_value(&data)?)?;
+        let env: Self = from_json("Env", &serde_json::to_string(&data)?)?;
 
         Ok(env)
     }
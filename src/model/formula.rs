@@ -1,5 +1,8 @@
 use serde::{Serialize, Deserialize};
 
+use crate::BrewVersion;
+use crate::model::version::deserialize_pkg_version;
+
 /// `Formula` 包的结构体
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Formula {
@@ -58,6 +61,32 @@ impl Formula {
     pub fn is_installed(&self) -> bool {
         !self.installed.is_empty()
     }
+
+    /// 当前的稳定版本，包括 `revision` 和 `version_scheme`，比如 `gettext` 的 `0.23_1`
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::Package;
+    ///
+    /// let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+    /// let pkg = Package::from_all(&json_str).unwrap();
+    /// let gettext = pkg.formulae().iter().find(|f| f.name == "gettext").unwrap();
+    ///
+    /// let version = gettext.pkg_version().unwrap();
+    /// assert_eq!(version.to_string(), "0.23_1");
+    /// assert_eq!(version, gettext.installed[0].version);
+    /// ```
+    pub fn pkg_version(&self) -> Option<BrewVersion> {
+        let stable = self.versions.stable.as_ref()?;
+        let version = match self.revision {
+            0 => stable.clone(),
+            revision => BrewVersion::pkg_version(&format!("{stable}_{revision}")),
+        };
+        Some(version.with_scheme(self.version_scheme.max(0) as u32))
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -101,8 +130,8 @@ pub struct Requirement {
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Versions {
-    pub stable: Option<String>,
-    pub head: Option<String>,
+    pub stable: Option<BrewVersion>,
+    pub head: Option<BrewVersion>,
     pub bottle: bool,
 }
 
@@ -182,7 +211,8 @@ pub struct UsesFromMacOsBounds {
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Installed {
-    pub version: String,
+    #[serde(deserialize_with = "deserialize_pkg_version")]
+    pub version: BrewVersion,
     pub used_options: Vec<String>,
     pub built_as_bottle: bool,
     pub poured_from_bottle: bool,
@@ -195,9 +225,10 @@ pub struct Installed {
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct RuntimeDependency {
     pub full_name: String,
-    pub version: String,
+    pub version: BrewVersion,
     pub revision: i32,
-    pub pkg_version: String,
+    #[serde(deserialize_with = "deserialize_pkg_version")]
+    pub pkg_version: BrewVersion,
     pub declared_directly: bool,
 }
 
@@ -230,11 +261,14 @@ mod tests {
     fn test_all_to_formulae() {
         let tempfile = PathBuf::from("target/package.json");
         if !tempfile.exists() {
-            let pkg = info_all().unwrap();
-            pkg.to_file(&tempfile).unwrap();
+            if let Ok(pkg) = info_all() {
+                pkg.to_file(&tempfile).unwrap();
+            }
         }
+        // 没有安装 `brew` 的机器上使用示例数据
+        let path = if tempfile.exists() { tempfile } else { PathBuf::from("examples/data/all.json") };
 
-        let out = fs::read_to_string(&tempfile).expect("Failed read package.json");
+        let out = fs::read_to_string(&path).expect("Failed read package.json");
         let all_packages: HashMap<String, Value> = serde_json::from_str(&out).expect("Failed parse json string");
 
         for (key, value) in &all_packages {
@@ -246,7 +280,7 @@ mod tests {
             for val in &packages {
                 let name = &val["name"];
                 let text = serde_json::to_string_pretty(val).expect("Faield to string");
-                let pkg_m = serde_json::from_value::<Formula>(val.clone()).map_err(|e| anyhow::Error::new(e));
+                let pkg_m = serde_json::from_value::<Formula>(val.clone()).map_err(anyhow::Error::new);
                 match pkg_m{
                     Ok(pkg) => assert_eq!(&pkg.name, name),
                     Err(e) => {
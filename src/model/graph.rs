@@ -0,0 +1,450 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt,
+};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{Formula, Package};
+
+/// 依赖关系的类型，对应 `Formula` 中的依赖字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    /// `dependencies`
+    Required,
+
+    /// `recommended_dependencies`
+    Recommended,
+
+    /// `optional_dependencies`
+    Optional,
+
+    /// `build_dependencies`
+    Build,
+
+    /// `test_dependencies`
+    Test,
+
+    /// 安装记录 `installed.runtime_dependencies` 中直接声明的依赖，已经存在其他运行时依赖时不会重复添加
+    Runtime,
+}
+
+impl DependencyKind {
+    /// 安装后运行时需要的依赖类型
+    pub const RUNTIME: &'static [DependencyKind] = &[
+        DependencyKind::Required,
+        DependencyKind::Recommended,
+        DependencyKind::Runtime,
+    ];
+
+    /// 从源码安装时需要的依赖类型，运行时依赖加上构建依赖
+    pub const INSTALL: &'static [DependencyKind] = &[
+        DependencyKind::Required,
+        DependencyKind::Recommended,
+        DependencyKind::Build,
+        DependencyKind::Runtime,
+    ];
+
+    /// 全部依赖类型
+    pub const ALL: &'static [DependencyKind] = &[
+        DependencyKind::Required,
+        DependencyKind::Recommended,
+        DependencyKind::Optional,
+        DependencyKind::Build,
+        DependencyKind::Test,
+        DependencyKind::Runtime,
+    ];
+
+    /// 是否为运行时依赖
+    pub fn is_runtime(&self) -> bool {
+        Self::RUNTIME.contains(self)
+    }
+}
+
+impl fmt::Display for DependencyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DependencyKind::Required => "required",
+            DependencyKind::Recommended => "recommended",
+            DependencyKind::Optional => "optional",
+            DependencyKind::Build => "build",
+            DependencyKind::Test => "test",
+            DependencyKind::Runtime => "runtime",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// 依赖关系，`from` 依赖 `to`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+/// `Formula` 之间的依赖关系图，节点为 `Formula` 的名称
+///
+/// 通过 `Formula` 添加时，第三方 tap 中的全名（比如 `user/tap/foo`）会统一为短名称 `foo`
+///
+/// Examples
+///
+/// ```
+/// use std::fs;
+///
+/// use homebrew::{DependencyGraph, DependencyKind, Package};
+///
+/// let json_str = fs::read_to_string("examples/data/all.json").unwrap();
+/// let graph = DependencyGraph::from_package(&Package::from_all(&json_str).unwrap());
+///
+/// // 哪些包会引入 openssl@3
+/// let users = graph.dependents_closure("openssl@3", DependencyKind::RUNTIME);
+/// assert!(users.contains("wget") && users.contains("rust"));
+///
+/// let deps = graph.closure("wget", &[DependencyKind::Required]);
+/// assert!(deps.contains("openssl@3"));
+/// assert!(!deps.contains("pkgconf"));
+///
+/// let order = graph.topological_order(DependencyKind::INSTALL).unwrap();
+/// let pos = |name: &str| order.iter().position(|n| n == name).unwrap();
+/// assert!(pos("openssl@3") < pos("wget"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DependencyGraph {
+    nodes: BTreeSet<String>,
+    edges: BTreeSet<DependencyEdge>,
+    /// 每个包直接依赖的包和依赖类型，用于查询时不需要遍历全部的边
+    forward: BTreeMap<String, BTreeSet<(String, DependencyKind)>>,
+    /// 直接依赖每个包的包和依赖类型
+    reverse: BTreeMap<String, BTreeSet<(String, DependencyKind)>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 通过 [`Package`] 中的全部 `Formula` 新建，比如 [`crate::info_all`] 的结果
+    pub fn from_package(pkg: &Package) -> Self {
+        Self::from_formulae(pkg.formulae())
+    }
+
+    /// 通过 `Formula` 列表新建
+    pub fn from_formulae<'a, I>(formulae: I) -> Self
+        where I: IntoIterator<Item = &'a Formula>
+    {
+        let mut graph = Self::new();
+        for f in formulae {
+            graph.add_formula(f);
+        }
+        graph
+    }
+
+    /// 添加一个 `Formula` 及其全部依赖
+    pub fn add_formula(&mut self, formula: &Formula) -> &mut Self {
+        let name = short_name(&formula.name);
+        self.add_node(name);
+        let declared = [
+            (&formula.dependencies, DependencyKind::Required),
+            (&formula.recommended_dependencies, DependencyKind::Recommended),
+            (&formula.optional_dependencies, DependencyKind::Optional),
+            (&formula.build_dependencies, DependencyKind::Build),
+            (&formula.test_dependencies, DependencyKind::Test),
+        ];
+        for (deps, kind) in declared {
+            for dep in deps {
+                self.add_edge(name, short_name(dep), kind);
+            }
+        }
+        let runtime = formula.installed.iter()
+            .flat_map(|i| &i.runtime_dependencies)
+            .filter(|d| d.declared_directly);
+        for dep in runtime {
+            let to = short_name(&dep.full_name);
+            let exists = self.forward.get(name)
+                .is_some_and(|deps| deps.iter().any(|(t, kind)| t == to && kind.is_runtime()));
+            if !exists {
+                self.add_edge(name, to, DependencyKind::Runtime);
+            }
+        }
+        self
+    }
+
+    /// 添加节点
+    pub fn add_node<S: Into<String>>(&mut self, name: S) -> &mut Self {
+        self.nodes.insert(name.into());
+        self
+    }
+
+    /// 添加依赖关系，`from` 依赖 `to`，节点不存在时会自动添加
+    pub fn add_edge<F: Into<String>, T: Into<String>>(&mut self, from: F, to: T, kind: DependencyKind) -> &mut Self {
+        let (from, to) = (from.into(), to.into());
+        self.nodes.insert(from.clone());
+        self.nodes.insert(to.clone());
+        self.forward.entry(from.clone()).or_default().insert((to.clone(), kind));
+        self.reverse.entry(to.clone()).or_default().insert((from.clone(), kind));
+        self.edges.insert(DependencyEdge { from, to, kind });
+        self
+    }
+
+    /// 全部节点，按名称排序
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(String::as_str)
+    }
+
+    /// 全部依赖关系
+    pub fn edges(&self) -> impl Iterator<Item = &DependencyEdge> {
+        self.edges.iter()
+    }
+
+    /// 是否包含节点
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.contains(name)
+    }
+
+    /// `name` 直接依赖的包，按名称和依赖类型排序
+    pub fn dependencies(&self, name: &str) -> Vec<DependencyEdge> {
+        self.forward.get(name).into_iter().flatten()
+            .map(|(to, kind)| DependencyEdge { from: name.to_string(), to: to.clone(), kind: *kind })
+            .collect()
+    }
+
+    /// 直接依赖 `name` 的包，按名称和依赖类型排序
+    pub fn dependents(&self, name: &str) -> Vec<DependencyEdge> {
+        self.reverse.get(name).into_iter().flatten()
+            .map(|(from, kind)| DependencyEdge { from: from.clone(), to: name.to_string(), kind: *kind })
+            .collect()
+    }
+
+    /// `name` 通过 `kinds` 类型的依赖间接或直接依赖的全部包，不包括自身
+    pub fn closure(&self, name: &str, kinds: &[DependencyKind]) -> BTreeSet<String> {
+        walk(&self.forward, name, kinds)
+    }
+
+    /// 通过 `kinds` 类型的依赖间接或直接依赖 `name` 的全部包，不包括自身
+    pub fn dependents_closure(&self, name: &str, kinds: &[DependencyKind]) -> BTreeSet<String> {
+        walk(&self.reverse, name, kinds)
+    }
+
+    /// 只考虑 `kinds` 类型依赖的拓扑排序，依赖排在依赖它的包前面
+    ///
+    /// 作为安装顺序时通常使用 [`DependencyKind::INSTALL`]，`test` 等依赖之间的环不会影响结果，
+    /// 存在循环依赖时返回 [`DependencyGraph::cycles`] 中的第一个环
+    pub fn topological_order(&self, kinds: &[DependencyKind]) -> Result<Vec<String>, Vec<String>> {
+        let mut pending: BTreeMap<&str, usize> = self.nodes.iter().map(|n| (n.as_str(), 0)).collect();
+        let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in self.unique_edges(kinds) {
+            *pending.entry(from).or_default() += 1;
+            dependents.entry(to).or_default().push(from);
+        }
+        let mut ready: VecDeque<&str> = pending.iter().filter(|(_, n)| **n == 0).map(|(k, _)| *k).collect();
+        let mut order = Vec::new();
+        while let Some(node) = ready.pop_front() {
+            order.push(node.to_string());
+            for dependent in dependents.get(node).into_iter().flatten() {
+                let n = pending.get_mut(dependent).expect("node exists");
+                *n -= 1;
+                if *n == 0 {
+                    ready.push_back(dependent);
+                }
+            }
+        }
+        if order.len() == self.nodes.len() {
+            Ok(order)
+        } else {
+            Err(self.cycles(kinds).into_iter().next().unwrap_or_default())
+        }
+    }
+
+    /// 只考虑 `kinds` 类型依赖时的全部循环依赖，每个环中的包按名称排序
+    pub fn cycles(&self, kinds: &[DependencyKind]) -> Vec<Vec<String>> {
+        let mut adjacent: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut self_loops = BTreeSet::new();
+        for (from, to) in self.unique_edges(kinds) {
+            adjacent.entry(from).or_default().push(to);
+            if from == to {
+                self_loops.insert(from);
+            }
+        }
+        let mut tarjan = Tarjan {
+            adjacent: &adjacent,
+            index: BTreeMap::new(),
+            low: BTreeMap::new(),
+            stack: Vec::new(),
+            on_stack: BTreeSet::new(),
+            components: Vec::new(),
+        };
+        for node in &self.nodes {
+            if !tarjan.index.contains_key(node.as_str()) {
+                tarjan.visit(node);
+            }
+        }
+        tarjan.components.into_iter()
+            .filter(|c| c.len() > 1 || self_loops.contains(c[0]))
+            .map(|mut c| {
+                c.sort();
+                c.into_iter().map(String::from).collect()
+            })
+            .collect()
+    }
+
+    /// 导出为 Graphviz 的 DOT 格式，非运行时依赖为虚线并标注类型
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph dependencies {".to_string()];
+        for node in &self.nodes {
+            lines.push(format!("    {node:?};"));
+        }
+        for e in &self.edges {
+            let attrs = match e.kind {
+                DependencyKind::Required => String::new(),
+                k if k.is_runtime() => format!(" [label=\"{k}\"]"),
+                k => format!(" [label=\"{k}\", style=dashed]"),
+            };
+            lines.push(format!("    {:?} -> {:?}{attrs};", e.from, e.to));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    /// 导出为 Mermaid 流程图，非运行时依赖为虚线并标注类型
+    pub fn to_mermaid(&self) -> String {
+        // 包名中的 `@` 等字符不能作为 Mermaid 的节点 id
+        let ids: BTreeMap<&str, String> = self.nodes.iter()
+            .enumerate()
+            .map(|(i, n)| (n.as_str(), format!("n{i}")))
+            .collect();
+        let mut lines = vec!["graph TD".to_string()];
+        for (name, id) in &ids {
+            lines.push(format!("    {id}[\"{name}\"]"));
+        }
+        for e in &self.edges {
+            let arrow = match e.kind {
+                DependencyKind::Required => "-->".to_string(),
+                k if k.is_runtime() => format!("-->|{k}|"),
+                k => format!("-.->|{k}|"),
+            };
+            lines.push(format!("    {} {arrow} {}", ids[e.from.as_str()], ids[e.to.as_str()]));
+        }
+        lines.join("\n")
+    }
+
+    /// `kinds` 类型的边，去掉依赖类型后去重
+    fn unique_edges(&self, kinds: &[DependencyKind]) -> BTreeSet<(&str, &str)> {
+        self.edges.iter()
+            .filter(|e| kinds.contains(&e.kind))
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect()
+    }
+}
+
+/// 计算强连通分量
+struct Tarjan<'a> {
+    adjacent: &'a BTreeMap<&'a str, Vec<&'a str>>,
+    index: BTreeMap<&'a str, usize>,
+    low: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    components: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, node: &'a str) {
+        let i = self.index.len();
+        self.index.insert(node, i);
+        self.low.insert(node, i);
+        self.stack.push(node);
+        self.on_stack.insert(node);
+        for next in self.adjacent.get(node).into_iter().flatten() {
+            if !self.index.contains_key(next) {
+                self.visit(next);
+                let low = self.low[node].min(self.low[next]);
+                self.low.insert(node, low);
+            } else if self.on_stack.contains(next) {
+                let low = self.low[node].min(self.index[next]);
+                self.low.insert(node, low);
+            }
+        }
+        if self.low[node] == self.index[node] {
+            let mut component = Vec::new();
+            while let Some(n) = self.stack.pop() {
+                self.on_stack.remove(n);
+                component.push(n);
+                if n == node {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+/// 沿着 `adjacent` 中 `kinds` 类型的边广度优先遍历，不包括起点
+fn walk(adjacent: &BTreeMap<String, BTreeSet<(String, DependencyKind)>>, name: &str, kinds: &[DependencyKind]) -> BTreeSet<String> {
+    let mut seen = BTreeSet::new();
+    let mut queue = VecDeque::from([name]);
+    while let Some(node) = queue.pop_front() {
+        let next = adjacent.get(node).into_iter().flatten().filter(|(_, kind)| kinds.contains(kind));
+        for (next, _) in next {
+            if next != name && seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// 第三方 tap 中的全名转为短名称，比如 `user/tap/foo` 转为 `foo`
+pub(crate) fn short_name(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graph() {
+        let mut graph = DependencyGraph::new();
+        graph.add_edge("a", "b", DependencyKind::Required)
+            .add_edge("b", "c", DependencyKind::Build)
+            .add_edge("c", "a", DependencyKind::Required)
+            .add_edge("c", "d", DependencyKind::Required)
+            .add_edge("e", "e", DependencyKind::Test);
+        assert_eq!(graph.cycles(DependencyKind::ALL), [vec!["a", "b", "c"], vec!["e"]]);
+        assert_eq!(graph.topological_order(DependencyKind::INSTALL), Err(vec!["a".to_string(), "b".to_string(), "c".to_string()]));
+        // 不考虑构建依赖时没有环，`test` 依赖的自环也被忽略
+        assert!(graph.cycles(DependencyKind::RUNTIME).is_empty());
+        assert_eq!(graph.topological_order(DependencyKind::RUNTIME).unwrap(), ["b", "d", "e", "a", "c"]);
+        assert_eq!(graph.closure("a", &[DependencyKind::Required]), BTreeSet::from(["b".to_string()]));
+        assert_eq!(graph.closure("a", DependencyKind::ALL).len(), 3);
+        assert_eq!(graph.dependencies("c"), [
+            DependencyEdge { from: "c".to_string(), to: "a".to_string(), kind: DependencyKind::Required },
+            DependencyEdge { from: "c".to_string(), to: "d".to_string(), kind: DependencyKind::Required },
+        ]);
+        let dependents: Vec<_> = graph.dependents("a").into_iter().map(|e| e.from).collect();
+        assert_eq!(dependents, ["c"]);
+
+        let mut graph = DependencyGraph::new();
+        graph.add_edge("wget", "openssl@3", DependencyKind::Required)
+            .add_edge("wget", "homebrew/core/pkgconf", DependencyKind::Build);
+        assert_eq!(graph.topological_order(DependencyKind::INSTALL).unwrap(), ["homebrew/core/pkgconf", "openssl@3", "wget"]);
+        assert_eq!(graph.to_dot(), [
+            "digraph dependencies {",
+            "    \"homebrew/core/pkgconf\";",
+            "    \"openssl@3\";",
+            "    \"wget\";",
+            "    \"wget\" -> \"homebrew/core/pkgconf\" [label=\"build\", style=dashed];",
+            "    \"wget\" -> \"openssl@3\";",
+            "}",
+        ].join("\n"));
+        assert_eq!(graph.to_mermaid(), [
+            "graph TD",
+            "    n0[\"homebrew/core/pkgconf\"]",
+            "    n1[\"openssl@3\"]",
+            "    n2[\"wget\"]",
+            "    n2 -.->|build| n0",
+            "    n2 --> n1",
+        ].join("\n"));
+    }
+}
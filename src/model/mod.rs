@@ -1,5 +1,10 @@
 pub mod cask;
 pub mod config;
 pub mod formula;
+pub mod autoremove;
+pub mod graph;
+pub mod outdated;
+pub mod version;
+pub mod uses;
 pub mod package;
 pub mod service;
@@ -0,0 +1,154 @@
+use serde::{Serialize, Deserialize};
+
+use crate::model::cask::AutoUpdates;
+use crate::trace::from_json;
+use crate::{BrewVersion, Cask, Formula, Package};
+
+/// 可以升级的包，`brew outdated --json=v2` 中的一项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outdated {
+    pub name: String,
+
+    /// 已安装的版本
+    pub installed_versions: Vec<BrewVersion>,
+
+    /// 最新的版本
+    pub current_version: BrewVersion,
+
+    /// 是否通过 `brew pin` 固定了版本，`Cask` 总是 `false`
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// 固定的版本
+    #[serde(default)]
+    pub pinned_version: Option<BrewVersion>,
+
+    /// 是否为 `Cask`
+    #[serde(default)]
+    pub cask: bool,
+}
+
+#[derive(Deserialize)]
+struct OutdatedJson {
+    formulae: Vec<Outdated>,
+    casks: Vec<Outdated>,
+}
+
+impl Outdated {
+    /// 解析 `brew outdated --json=v2` 的输出，`Formula` 在前，`Cask` 在后
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::Outdated;
+    ///
+    /// let json_str = fs::read_to_string("examples/data/outdated.json").unwrap();
+    /// let outdated = Outdated::from(&json_str).unwrap();
+    ///
+    /// let tree = &outdated[2];
+    /// assert_eq!(tree.name, "tree");
+    /// assert!(tree.pinned && !tree.cask);
+    /// assert_eq!(tree.pinned_version.as_ref().unwrap().as_str(), "2.1.3");
+    /// assert!(outdated[3].cask);
+    /// ```
+    pub fn from(json_str: &str) -> anyhow::Result<Vec<Self>> {
+        let json: OutdatedJson = from_json("Outdated", json_str)?;
+        let formulae = json.formulae.into_iter().map(Self::with_pkg_versions);
+        let casks = json.casks.into_iter().map(|c| Self { cask: true, ..c });
+        Ok(formulae.chain(casks).collect())
+    }
+
+    /// `Formula` 的版本中末尾的 `_数字` 为 `revision`，见 [`BrewVersion::pkg_version`]
+    fn with_pkg_versions(self) -> Self {
+        let pkg = |v: &BrewVersion| BrewVersion::pkg_version(v.as_str());
+        Self {
+            installed_versions: self.installed_versions.iter().map(pkg).collect(),
+            current_version: pkg(&self.current_version),
+            pinned_version: self.pinned_version.as_ref().map(pkg),
+            ..self
+        }
+    }
+
+    fn from_formula(formula: &Formula) -> Option<Self> {
+        let current = formula.pkg_version()?;
+        // `brew info --json=v2` 的安装记录中没有 `version_scheme`，只能认为和当前的相同，见 `Package::outdated`
+        let scheme = current.version_scheme();
+        let installed: Vec<BrewVersion> = formula.installed.iter()
+            .map(|i| i.version.clone().with_scheme(scheme))
+            .collect();
+        let newest = installed.iter().filter(|v| !v.is_head()).max()?;
+        if *newest >= current {
+            return None;
+        }
+        let pinned_version = formula.pinned.then(|| match &formula.linked_keg {
+            Some(keg) => BrewVersion::pkg_version(keg),
+            None => newest.clone(),
+        });
+        Some(Self {
+            name: formula.name.clone(),
+            installed_versions: installed,
+            current_version: current,
+            pinned: formula.pinned,
+            pinned_version,
+            cask: false,
+        })
+    }
+
+    fn from_cask(cask: &Cask, greedy: bool) -> Option<Self> {
+        let installed = cask.installed.as_ref()?;
+        let auto_updates = matches!(cask.auto_updates, Some(AutoUpdates::Bool(true)));
+        if (auto_updates || cask.version.is_latest()) && !greedy {
+            return None;
+        }
+        // 和 `brew` 一样，版本号不同就认为可以升级，`latest` 在 `--greedy` 时总是可以升级
+        if !cask.version.is_latest() && installed.as_str() == cask.version.as_str() {
+            return None;
+        }
+        Some(Self {
+            name: cask.token.clone(),
+            installed_versions: vec![installed.clone()],
+            current_version: cask.version.clone(),
+            pinned: false,
+            pinned_version: None,
+            cask: true,
+        })
+    }
+}
+
+impl Package {
+    /// 离线计算可以升级的包，相当于 `brew outdated [--greedy]`
+    ///
+    /// `Formula` 比较已安装的版本和包括 `revision`、`version_scheme` 的稳定版本，`HEAD` 版本不会被认为过期；
+    /// `Cask` 比较已安装的版本和当前版本，`auto_updates` 和 `latest` 的 `Cask` 只在 `greedy` 为 `true` 时检查
+    ///
+    /// 结果和 [`Outdated::from`] 一样 `Formula` 在前，`Cask` 在后，分别按名称排序
+    ///
+    /// 注意 `brew info --json=v2` 的安装记录中没有 `version_scheme`，这里认为已安装的版本和当前的 `version_scheme` 相同，
+    /// 所以 `version_scheme` 升级后版本号变小的 `Formula` 不会出现在结果中，而 `brew outdated` 会认为它过期，
+    /// 需要和 `brew` 完全一致时请使用 [`crate::outdated`]
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::Package;
+    ///
+    /// let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+    /// let pkg = Package::from_all(&json_str).unwrap();
+    ///
+    /// let names = |greedy| pkg.outdated(greedy).into_iter().map(|o| o.name).collect::<Vec<_>>();
+    /// assert_eq!(names(false), ["curl", "openssl@3", "tree", "kitty"]);
+    /// assert_eq!(names(true), ["curl", "openssl@3", "tree", "kitty", "rustdesk"]);
+    /// ```
+    pub fn outdated(&self, greedy: bool) -> Vec<Outdated> {
+        let mut formulae: Vec<Outdated> = self.formulae().iter().filter_map(Outdated::from_formula).collect();
+        let mut casks: Vec<Outdated> = self.casks().iter().filter_map(|c| Outdated::from_cask(c, greedy)).collect();
+        formulae.sort_by(|a, b| a.name.cmp(&b.name));
+        casks.sort_by(|a, b| a.name.cmp(&b.name));
+        formulae.extend(casks);
+        formulae
+    }
+}
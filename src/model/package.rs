@@ -4,6 +4,7 @@ use serde::{Serialize, Deserialize};
 use serde_json::{Value, Result};
 
 use crate::{Cask, Formula};
+use crate::trace::from_json;
 
 /// 运行 `brew info [name] --json=v2` 命令 `json` 结果反序列的结构体
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -122,7 +123,7 @@ impl Package {
     /// }
     /// ```
     pub fn from_all(json_str: &str) -> anyhow::Result<Self> {
-        let mut pkg: Package = serde_json::from_str(json_str)?;
+        let mut pkg: Package = from_json("Package", json_str)?;
         // 给 value 赋值
         let value: HashMap<String, Value> = serde_json::from_str(json_str)?;
         pkg.value = value;
@@ -2,6 +2,8 @@ use serde::{Serialize, Deserialize};
 use std::str::FromStr;
 use std::fmt;
 
+use crate::trace::from_json;
+
 // 定义状态枚举
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "lowercase")] // 使得枚举可以接受小写字符串
@@ -69,8 +71,7 @@ impl Service {
     /// assert_eq!(s.name, "mongodb-community@7.0");
     /// ```
     pub fn from(json_str: &str) -> anyhow::Result<Self> {
-        let pkg: Self = serde_json::from_str(json_str)?;
-        Ok(pkg)
+        from_json("Service", json_str)
     }
 }
 
@@ -133,7 +134,6 @@ impl ServiceInfo {
     /// assert_eq!(s.status, ServiceStatus::None);
     /// ```
     pub fn from(json_str: &str) -> anyhow::Result<Self> {
-        let pkg: Self = serde_json::from_str(json_str)?;
-        Ok(pkg)
+        from_json("ServiceInfo", json_str)
     }
 }
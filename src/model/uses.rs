@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{DependencyGraph, DependencyKind, Formula, Package};
+
+use super::graph::short_name;
+
+/// 依赖某个包的 `Formula`，由 [`Package::uses`] 得到
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependent {
+    pub name: String,
+    pub full_name: String,
+
+    /// 依赖关系的类型，间接依赖时为它和依赖链上下一个包之间的类型
+    pub kind: DependencyKind,
+
+    /// 是否直接依赖
+    pub direct: bool,
+
+    /// 是否已经安装
+    pub installed: bool,
+
+    /// 是否为用户主动安装
+    pub installed_on_request: bool,
+
+    /// 是否作为其他包的依赖被安装
+    pub installed_as_dependency: bool,
+}
+
+impl Dependent {
+    fn new(formula: Option<&Formula>, name: &str, kind: DependencyKind, direct: bool) -> Self {
+        let receipts = formula.map(|f| f.installed.as_slice()).unwrap_or_default();
+        Self {
+            name: name.to_string(),
+            full_name: formula.map(|f| f.full_name.clone()).unwrap_or_else(|| name.to_string()),
+            kind,
+            direct,
+            installed: !receipts.is_empty(),
+            installed_on_request: receipts.iter().any(|i| i.installed_on_request),
+            installed_as_dependency: receipts.iter().any(|i| i.installed_as_dependency),
+        }
+    }
+}
+
+impl Package {
+    /// 离线计算哪些 `Formula` 依赖 `name`，相当于 `brew uses [--installed] [--recursive] [name]`
+    ///
+    /// 只考虑运行时依赖 [`DependencyKind::RUNTIME`]，已安装的包会同时使用安装记录中的 `runtime_dependencies`
+    ///
+    /// `installed_only` 为 `true` 时只返回已安装的包，`recursive` 为 `true` 时包括间接依赖，结果按名称排序
+    ///
+    /// Examples
+    ///
+    /// ```
+    /// use std::fs;
+    ///
+    /// use homebrew::{DependencyKind, Package};
+    ///
+    /// let json_str = fs::read_to_string("examples/data/installed.json").unwrap();
+    /// let pkg = Package::from_all(&json_str).unwrap();
+    ///
+    /// // 卸载 libunistring 会影响哪些包
+    /// let names = |deps: Vec<homebrew::Dependent>| deps.into_iter().map(|d| d.name).collect::<Vec<_>>();
+    /// assert_eq!(names(pkg.uses("libunistring", true, false)), ["libidn2", "wget"]);
+    /// assert_eq!(names(pkg.uses("libunistring", true, true)), ["curl", "libidn2", "wget"]);
+    ///
+    /// let curl = pkg.uses("libunistring", true, true).remove(0);
+    /// assert!(!curl.direct && curl.installed_on_request);
+    /// assert_eq!(curl.kind, DependencyKind::Required);
+    /// ```
+    pub fn uses(&self, name: &str, installed_only: bool, recursive: bool) -> Vec<Dependent> {
+        let formulae = self.formulae_by_name();
+        let graph = DependencyGraph::from_formulae(self.formulae().iter()
+            .filter(|f| !installed_only || f.is_installed()));
+        let target = short_name(name);
+
+        let mut found: BTreeMap<String, Dependent> = BTreeMap::new();
+        let mut queue = VecDeque::from([(target.to_string(), true)]);
+        while let Some((node, direct)) = queue.pop_front() {
+            // 边按 `DependencyKind` 的顺序排列，同一个包有多种依赖关系时取第一个
+            for edge in graph.dependents(&node).into_iter().filter(|e| e.kind.is_runtime()) {
+                if edge.from == target || found.contains_key(&edge.from) {
+                    continue;
+                }
+                let formula = formulae.get(edge.from.as_str()).copied();
+                found.insert(edge.from.clone(), Dependent::new(formula, &edge.from, edge.kind, direct));
+                if recursive {
+                    queue.push_back((edge.from, false));
+                }
+            }
+        }
+        found.into_values().collect()
+    }
+
+    /// 给 `brew uses --installed` 返回的包名补充依赖类型和安装状态，结果的顺序和 `names` 一致
+    ///
+    /// 本地计算没有找到的包 `kind` 为 [`DependencyKind::Runtime`]，`direct` 只在 `recursive` 为 `false` 时为 `true`
+    pub(crate) fn annotate_uses(&self, name: &str, recursive: bool, names: &[String]) -> Vec<Dependent> {
+        let formulae = self.formulae_by_name();
+        let mut found: BTreeMap<String, Dependent> = self.uses(name, true, recursive).into_iter()
+            .map(|d| (d.name.clone(), d))
+            .collect();
+        names.iter()
+            .map(|n| {
+                let n = short_name(n);
+                found.remove(n).unwrap_or_else(|| {
+                    Dependent::new(formulae.get(n).copied(), n, DependencyKind::Runtime, !recursive)
+                })
+            })
+            .collect()
+    }
+
+    /// 按短名称索引全部 `Formula`
+    fn formulae_by_name(&self) -> BTreeMap<&str, &Formula> {
+        self.formulae().iter()
+            .map(|f| (short_name(&f.name), f))
+            .collect()
+    }
+}
@@ -0,0 +1,325 @@
+use std::{cmp::Ordering, convert::Infallible, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// `brew` 的版本号，比较规则和 `brew` 的 `Version`、`PkgVersion` 一致
+///
+/// - 按数字、字母拆分后逐段比较，`1.10` 大于 `1.9`，`1.0` 等于 `1`
+/// - `alpha` < `beta` < `pre` < `rc` < 正式版 < `p`/`patch`/`post`
+/// - 通过 [`BrewVersion::pkg_version`] 解析 `Formula` 的版本时，末尾的 `_1` 为 `revision`，版本相同时再比较 `revision`，
+///   `Cask` 的版本中经常有下划线，不会被拆分
+/// - 很长的数字不会溢出，按位数和字面值比较
+/// - `version_scheme` 不同时只比较 `version_scheme`，见 [`BrewVersion::with_scheme`]
+/// - `Cask` 的 `1.2.3,456` 中的逗号作为分隔符，`latest` 和 `HEAD` 大于其他任何版本
+///
+/// Examples
+///
+/// ```
+/// use homebrew::BrewVersion;
+///
+/// let v = |s: &str| BrewVersion::new(s);
+///
+/// assert!(v("1.10") > v("1.9"));
+/// assert!(v("1.0rc1") < v("1.0"));
+/// assert!(BrewVersion::pkg_version("0.23") < BrewVersion::pkg_version("0.23_1"));
+/// assert!(v("1.2_3") > v("1.2.1"));
+/// assert!(v("2.0") < v("1.0").with_scheme(1));
+/// assert!(v("0.38.1,1234") > v("0.38.1,999"));
+/// assert!(v("latest") > v("99"));
+/// assert_eq!(v("1.0"), v("1"));
+/// assert_eq!(v("0.23_1").to_string(), "0.23_1");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BrewVersion {
+    raw: String,
+    scheme: u32,
+    revision: u32,
+    tokens: Vec<Token>,
+}
+
+impl BrewVersion {
+    /// 解析版本号，下划线和点一样只是分隔符，适用于 `Cask` 的版本
+    pub fn new(version: &str) -> Self {
+        let raw = version.trim().to_string();
+        let tokens = tokenize(&raw);
+        Self { raw, scheme: 0, revision: 0, tokens }
+    }
+
+    /// 解析 `Formula` 的版本号，比如安装记录中的版本，末尾的 `_数字` 会被当作 `revision`
+    pub fn pkg_version(version: &str) -> Self {
+        let raw = version.trim().to_string();
+        let (base, revision) = match raw.rsplit_once('_') {
+            Some((base, rev)) if !base.is_empty() && !rev.is_empty() && rev.bytes().all(|b| b.is_ascii_digit()) => {
+                (base, rev.parse().unwrap_or(0))
+            }
+            _ => (raw.as_str(), 0),
+        };
+        let tokens = tokenize(base);
+        Self { raw, scheme: 0, revision, tokens }
+    }
+
+    /// 设置 `version_scheme`，对应 `Formula` 的 `version_scheme` 字段
+    pub fn with_scheme(mut self, scheme: u32) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// 原始的版本号
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// 去掉 `revision` 的版本号
+    pub fn version(&self) -> &str {
+        match self.revision {
+            0 => &self.raw,
+            _ => self.raw.rsplit_once('_').map_or(self.raw.as_str(), |(base, _)| base),
+        }
+    }
+
+    /// `revision`，没有时为 0
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// `version_scheme`，没有设置时为 0
+    pub fn version_scheme(&self) -> u32 {
+        self.scheme
+    }
+
+    /// 是否为 `Cask` 的 `latest`
+    pub fn is_latest(&self) -> bool {
+        self.raw == "latest"
+    }
+
+    /// 是否为 `HEAD` 版本，比如 `HEAD-6aac197`
+    pub fn is_head(&self) -> bool {
+        self.raw.starts_with("HEAD")
+    }
+
+    /// `Cask` 版本中以逗号分隔的各部分，比如 `1.2.3,456` 为 `["1.2.3", "456"]`
+    pub fn csv(&self) -> Vec<&str> {
+        self.version().split(',').collect()
+    }
+
+    /// `latest` 和 `HEAD` 排在最后
+    fn rank(&self) -> u8 {
+        if self.is_latest() || self.is_head() { 1 } else { 0 }
+    }
+}
+
+impl Ord for BrewVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.scheme.cmp(&other.scheme)
+            .then_with(|| self.rank().cmp(&other.rank()))
+            .then_with(|| {
+                let len = self.tokens.len().max(other.tokens.len());
+                (0..len)
+                    .map(|i| compare(self.tokens.get(i), other.tokens.get(i)))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| self.revision.cmp(&other.revision))
+    }
+}
+
+impl PartialOrd for BrewVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for BrewVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for BrewVersion {}
+
+impl fmt::Display for BrewVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl FromStr for BrewVersion {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
+
+impl From<&str> for BrewVersion {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl Serialize for BrewVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for BrewVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(Self::new(&s))
+    }
+}
+
+/// 通过 [`BrewVersion::pkg_version`] 反序列化 `Formula` 的版本，用于 `#[serde(deserialize_with)]`
+pub(crate) fn deserialize_pkg_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BrewVersion, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Ok(BrewVersion::pkg_version(&s))
+}
+
+/// 预发布和补丁版本的类型，按先后顺序排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Alpha,
+    Beta,
+    Pre,
+    Rc,
+    Patch,
+    Post,
+}
+
+impl Stage {
+    fn is_prerelease(self) -> bool {
+        self < Stage::Patch
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    /// 去掉前导零的数字，`0` 为 `"0"`，长度不限
+    Numeric(String),
+    String(String),
+    Stage { stage: Stage, number: u64, text: String },
+}
+
+/// 按数字和字母拆分，其他字符都作为分隔符
+fn tokenize(version: &str) -> Vec<Token> {
+    let lower = version.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i].is_ascii_digit() {
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits = lower[start..i].trim_start_matches('0');
+            tokens.push(Token::Numeric(if digits.is_empty() { "0" } else { digits }.to_string()));
+        } else if bytes[i].is_ascii_alphabetic() {
+            while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            let word = &lower[start..i];
+            let digits_end = i + bytes[i..].iter().take_while(|b| b.is_ascii_digit()).count();
+            let has_number = digits_end > i;
+            let stage = match word {
+                "alpha" => Some(Stage::Alpha),
+                "a" if has_number => Some(Stage::Alpha),
+                "beta" => Some(Stage::Beta),
+                "b" if has_number => Some(Stage::Beta),
+                "pre" => Some(Stage::Pre),
+                "rc" => Some(Stage::Rc),
+                "p" | "patch" => Some(Stage::Patch),
+                "post" if has_number => Some(Stage::Post),
+                _ => None,
+            };
+            match stage {
+                Some(stage) => {
+                    let number = lower[i..digits_end].parse().unwrap_or(0);
+                    tokens.push(Token::Stage { stage, number, text: lower[start..digits_end].to_string() });
+                    i = digits_end;
+                }
+                None => tokens.push(Token::String(word.to_string())),
+            }
+        } else {
+            i += 1;
+        }
+    }
+    tokens
+}
+
+/// 比较同一位置的两段，长度不同时缺少的一方为 `None`
+fn compare(a: Option<&Token>, b: Option<&Token>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(t)) => compare_missing(t).reverse(),
+        (Some(t), None) => compare_missing(t),
+        (Some(a), Some(b)) => compare_token(a, b),
+    }
+}
+
+/// 一段和缺少的一段比较，`1.0` 等于 `1`，`1.0rc1` 小于 `1.0`，`1.0a` 和 `1.0p1` 大于 `1.0`
+fn compare_missing(token: &Token) -> Ordering {
+    match token {
+        Token::Numeric(n) if n == "0" => Ordering::Equal,
+        Token::Stage { stage, .. } if stage.is_prerelease() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+fn compare_token(a: &Token, b: &Token) -> Ordering {
+    use Token::*;
+
+    match (a, b) {
+        // 没有前导零，位数多的更大，位数相同时按字面值比较
+        (Numeric(a), Numeric(b)) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+        (Numeric(_), _) => Ordering::Greater,
+        (_, Numeric(_)) => Ordering::Less,
+        (Stage { stage: sa, number: na, text: ta }, Stage { stage: sb, number: nb, text: tb }) => {
+            if sa == sb {
+                na.cmp(nb)
+            } else if sa.is_prerelease() || sb.is_prerelease() {
+                sa.cmp(sb)
+            } else {
+                // `patch` 和 `post` 之间没有先后，按文本比较
+                ta.cmp(tb)
+            }
+        }
+        (String(ta), Stage { text: tb, .. })
+        | (Stage { text: ta, .. }, String(tb))
+        | (String(ta), String(tb)) => ta.cmp(tb),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_order() {
+        let ordered = [
+            "0.9", "1.0alpha", "1.0a2", "1.0beta1", "1.0b2", "1.0pre", "1.0rc1", "1.0rc2", "1.0",
+            "1.0p1", "1.0.1", "1.2", "1.10", "1.99999999999999999999", "1.100000000000000000000", "2024-11-26", "latest",
+        ];
+        for pair in ordered.windows(2) {
+            let (a, b) = (BrewVersion::new(pair[0]), BrewVersion::new(pair[1]));
+            assert!(a < b, "{a} < {b}");
+        }
+
+        let v = BrewVersion::pkg_version("0.23_1");
+        assert_eq!((v.version(), v.revision()), ("0.23", 1));
+        assert!(BrewVersion::pkg_version("1.0") < BrewVersion::pkg_version("1.0_1"));
+        assert!(BrewVersion::pkg_version("1.0_1") < BrewVersion::pkg_version("1.0p1"));
+
+        // `Cask` 的版本中的下划线不是 `revision`
+        let v = BrewVersion::new("1.2_3");
+        assert_eq!((v.version(), v.revision()), ("1.2_3", 0));
+        assert!(v > BrewVersion::new("1.2.1"));
+        assert_eq!(BrewVersion::new("1.2.3,456").csv(), ["1.2.3", "456"]);
+        assert!(BrewVersion::new("HEAD-6aac197") > BrewVersion::new("9.9"));
+        assert_eq!(BrewVersion::new("1.0.0"), BrewVersion::new("1"));
+
+        let v: BrewVersion = serde_json::from_str(r#""3.4.0""#).unwrap();
+        assert_eq!(serde_json::to_string(&v).unwrap(), r#""3.4.0""#);
+    }
+}
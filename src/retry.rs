@@ -0,0 +1,181 @@
+use std::time::Duration;
+
+use crate::error::BrewError;
+
+/// 可以重试的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryClass {
+    /// `curl: (xx) ...`，见 [`BrewError::CurlFailed`]
+    Curl,
+
+    /// `fatal: unable to access ...`，见 [`BrewError::GitFetchFailed`]
+    Git,
+
+    /// 其他 `brew` 进程正在执行，见 [`BrewError::Locked`]
+    Locked,
+
+    /// 命令超时，见 [`BrewError::Timeout`]
+    Timeout,
+}
+
+impl RetryClass {
+    /// 错误所属的类型，不可重试的错误返回 `None`
+    pub fn of(err: &BrewError) -> Option<Self> {
+        match err {
+            BrewError::CurlFailed { .. } => Some(RetryClass::Curl),
+            BrewError::GitFetchFailed { .. } => Some(RetryClass::Git),
+            BrewError::Locked { .. } => Some(RetryClass::Locked),
+            BrewError::Timeout { .. } => Some(RetryClass::Timeout),
+            _ => None,
+        }
+    }
+}
+
+/// 网络不稳定时的重试策略，通过 [`crate::Brew::set_retry`] 开启
+///
+/// 第 `n` 次重试前等待 `initial_backoff * multiplier^(n-1)`，最长不超过 `max_backoff`
+///
+/// Examples
+///
+/// ```
+/// use std::{sync::Arc, time::Duration};
+///
+/// use homebrew::{Brew, ReplayExecutor, RetryPolicy};
+///
+/// let replay: ReplayExecutor = r#"
+/// {"args":["update"],"env":{},"stdout":"","stderr":"fatal: unable to access 'https://mirrors.tuna.tsinghua.edu.cn/git/homebrew/brew.git/': Could not resolve host: mirrors.tuna.tsinghua.edu.cn\n","exit_code":1,"duration_ms":10}
+/// {"args":["update"],"env":{},"stdout":"Already up-to-date.\n","stderr":"","exit_code":0,"duration_ms":10}
+/// "#.parse().unwrap();
+///
+/// let out = Brew::new("update")
+///     .set_executor(Arc::new(replay))
+///     .set_retry(RetryPolicy::new(3).initial_backoff(Duration::from_millis(10)))
+///     .run().unwrap();
+///
+/// assert_eq!(out.stdout, "Already up-to-date.");
+/// assert_eq!(out.attempts.len(), 1);
+/// assert!(out.attempts[0].error.starts_with("fatal: unable to access"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// 最多执行的次数，包括第一次
+    pub max_attempts: u32,
+
+    /// 第一次重试前的等待时间
+    pub initial_backoff: Duration,
+
+    /// 每次重试后等待时间的倍数
+    pub multiplier: f64,
+
+    /// 最长的等待时间
+    pub max_backoff: Duration,
+
+    /// 可以重试的错误类型
+    pub retry_on: Vec<RetryClass>,
+}
+
+/// 失败后被重试的一次执行
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryAttempt {
+    /// 第几次执行，从 1 开始
+    pub attempt: u32,
+
+    /// 失败的原因，为错误的 `to_string()`
+    pub error: String,
+
+    /// 重试前等待的时间
+    pub delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl RetryPolicy {
+    /// 最多执行 `max_attempts` 次，默认只重试 [`RetryClass::Curl`] 和 [`RetryClass::Git`]
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_backoff: Duration::from_secs(30),
+            retry_on: vec![RetryClass::Curl, RetryClass::Git],
+        }
+    }
+
+    /// 设置第一次重试前的等待时间
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// 设置等待时间的倍数
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// 设置最长的等待时间
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// 设置可以重试的错误类型
+    pub fn retry_on<I: IntoIterator<Item = RetryClass>>(mut self, classes: I) -> Self {
+        self.retry_on = classes.into_iter().collect();
+        self
+    }
+
+    /// 错误是否可以重试
+    pub fn is_retryable(&self, err: &BrewError) -> bool {
+        RetryClass::of(err).is_some_and(|c| self.retry_on.contains(&c))
+    }
+
+    /// 第 `attempt` 次执行失败后的等待时间，`attempt` 从 1 开始
+    ///
+    /// 先按秒数计算并限制在 `max_backoff` 以内，`attempt` 很大或 `multiplier` 为无穷大时也不会溢出
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        if self.initial_backoff.is_zero() {
+            return Duration::ZERO;
+        }
+        let exp = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let factor = self.multiplier.max(1.0).powi(exp);
+        let secs = (self.initial_backoff.as_secs_f64() * factor).min(self.max_backoff.as_secs_f64());
+        Duration::try_from_secs_f64(secs).unwrap_or(self.max_backoff)
+    }
+
+    /// 第 `attempt` 次执行失败后，可以重试时返回等待时间
+    pub(crate) fn delay(&self, attempt: u32, err: &BrewError) -> Option<Duration> {
+        (attempt < self.max_attempts && self.is_retryable(err)).then(|| self.backoff(attempt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::new(5).max_backoff(Duration::from_secs(5));
+        let delays: Vec<u64> = (1..=5).map(|n| policy.backoff(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 5, 5]);
+
+        let err = BrewError::classify(Some(1), "curl: (28) Operation timed out after 30001 milliseconds\n");
+        assert_eq!(RetryClass::of(&err), Some(RetryClass::Curl));
+        assert_eq!(policy.delay(1, &err), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(5, &err), None);
+
+        let err = BrewError::classify(Some(1), "Error: No such keg: /opt/homebrew/Cellar/gotop\n");
+        assert_eq!(policy.delay(1, &err), None);
+
+        let policy = RetryPolicy::new(100).initial_backoff(Duration::from_secs(30)).multiplier(10.0);
+        assert_eq!(policy.backoff(19), policy.max_backoff);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_backoff);
+        let policy = policy.multiplier(f64::INFINITY);
+        assert_eq!(policy.backoff(2), policy.max_backoff);
+        assert_eq!(policy.initial_backoff(Duration::ZERO).backoff(u32::MAX), Duration::ZERO);
+    }
+}
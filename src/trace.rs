@@ -0,0 +1,152 @@
+//! 每次 `brew` 调用的埋点，包括 `tracing` feature 的 span 和 [`MetricsObserver`]
+
+use std::{cell::Cell, sync::Arc, time::Instant};
+
+use crate::error::BrewError;
+use crate::executor::Invocation;
+use crate::metrics::{command_kind, CallMetrics, MetricsObserver};
+use crate::retry::RetryAttempt;
+
+/// 一次 [`crate::Brew`] 调用的埋点，重试的多次执行都记录在同一个 span 中
+///
+/// 未开启 `tracing` feature 时只会通知 [`MetricsObserver`]
+pub(crate) struct CallSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    start: Instant,
+    observer: Option<Arc<dyn MetricsObserver>>,
+    command: String,
+    code: Cell<Option<i32>>,
+}
+
+impl CallSpan {
+    pub(crate) fn new(inv: &Invocation, observer: Option<Arc<dyn MetricsObserver>>) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "brew",
+                args = ?inv.args_lossy(),
+                env = ?inv.redacted_envs(),
+                duration_ms = tracing::field::Empty,
+                exit_code = tracing::field::Empty,
+                stdout_bytes = tracing::field::Empty,
+                stderr_bytes = tracing::field::Empty,
+            ),
+            start: Instant::now(),
+            observer,
+            command: command_kind(&inv.args),
+            code: Cell::new(None),
+        }
+    }
+
+    /// 在 span 中执行 `f`，执行器和解析输出时的日志都会记录在这次调用下
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    /// [`CallSpan::in_scope`] 的异步版本，每次 `poll` 时进入 span
+    #[cfg(all(feature = "async", feature = "tracing"))]
+    pub(crate) fn instrument<F: std::future::Future>(&self, fut: F) -> tracing::instrument::Instrumented<F> {
+        tracing::Instrument::instrument(fut, self.span.clone())
+    }
+
+    /// [`CallSpan::in_scope`] 的异步版本，未开启 `tracing` 时直接返回
+    #[cfg(all(feature = "async", not(feature = "tracing")))]
+    pub(crate) fn instrument<F: std::future::Future>(&self, fut: F) -> F {
+        fut
+    }
+
+    /// 进程退出后记录退出码和输出的字节数
+    pub(crate) fn exited(&self, code: Option<i32>, stdout_bytes: usize, stderr_bytes: usize) {
+        self.code.set(code);
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("duration_ms", self.start.elapsed().as_millis() as u64);
+            if let Some(code) = code {
+                self.span.record("exit_code", code);
+            }
+            self.span.record("stdout_bytes", stdout_bytes as u64);
+            self.span.record("stderr_bytes", stderr_bytes as u64);
+            tracing::debug!(parent: &self.span, "brew exited");
+        }
+        #[cfg(not(feature = "tracing"))]
+        let _ = (stdout_bytes, stderr_bytes);
+    }
+
+    /// 失败后准备重试
+    pub(crate) fn retry(&self, attempt: &RetryAttempt) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            parent: &self.span,
+            attempt = attempt.attempt,
+            delay_ms = attempt.delay.as_millis() as u64,
+            error = %attempt.error,
+            "retrying brew",
+        );
+        #[cfg(not(feature = "tracing"))]
+        let _ = attempt;
+    }
+
+    /// 调用成功，`attempts` 为执行的次数
+    pub(crate) fn succeeded(&self, attempts: u32) {
+        self.finish(None, attempts);
+    }
+
+    /// 调用最终返回了错误，`attempts` 为执行的次数，没有执行时为 0
+    pub(crate) fn failed(&self, err: &BrewError, attempts: u32) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("duration_ms", self.start.elapsed().as_millis() as u64);
+            tracing::warn!(parent: &self.span, error = %err, "brew failed");
+        }
+        self.finish(Some(err.kind()), attempts);
+    }
+
+    fn finish(&self, error: Option<&'static str>, attempts: u32) {
+        if let Some(observer) = &self.observer {
+            observer.record(&CallMetrics {
+                command: self.command.clone(),
+                duration: self.start.elapsed(),
+                code: self.code.get(),
+                error,
+                attempts,
+            });
+        }
+    }
+}
+
+/// 反序列化 `json`，失败时错误信息带上出错的路径，比如 `versions.stable: invalid type: ...`
+pub(crate) fn from_json<T>(type_name: &str, json_str: &str) -> anyhow::Result<T>
+    where T: serde::de::DeserializeOwned
+{
+    let de = &mut serde_json::Deserializer::from_str(json_str);
+    serde_path_to_error::deserialize(de).map_err(|e| {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(path = %e.path(), error = %e.inner(), "failed to parse {type_name}");
+        #[cfg(not(feature = "tracing"))]
+        let _ = type_name;
+        anyhow::Error::new(e)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{with_executor, MockExecutor, MockResponse, Service};
+
+    #[test]
+    fn test_json_path() {
+        let json_str = r#"{"name": "redis", "status": "started", "user": null, "file": "", "exit_code": "1"}"#;
+        let err = Service::from(json_str).unwrap_err();
+        assert!(err.to_string().starts_with("exit_code: invalid type"), "{err}");
+
+        let mock = MockExecutor::new()
+            .on(["services", "--json"], MockResponse::stdout(format!("[{json_str}]")));
+        let err = with_executor(Arc::new(mock), crate::services).unwrap_err();
+        assert!(err.to_string().contains("[0].exit_code: invalid type"), "{err}");
+    }
+}